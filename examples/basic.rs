@@ -148,6 +148,7 @@ impl Peer {
         let add = vec![parsec::Observation::Add {
             peer_id: peer_id.clone(),
             related_info: vec![],
+            weight: 1,
         }];
         self.vote_for_first_not_already_voted_for(&add);
     }
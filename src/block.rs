@@ -8,13 +8,17 @@
 
 use crate::{
     error::Error,
+    gossip::EventHash,
+    hash::Hash,
     id::{Proof, PublicId},
     network_event::NetworkEvent,
-    observation::Observation,
-    vote::Vote,
+    observation::{is_more_than_two_thirds_of_weight, ConsensusMode, Observation, ObservationHash},
+    serialise,
+    vote::{self, Vote},
 };
 use std::{
     collections::{vec_deque, BTreeMap, BTreeSet, VecDeque},
+    fmt::{self, Debug, Formatter},
     ops::{Deref, DerefMut},
 };
 
@@ -24,6 +28,11 @@ use std::{
 pub struct Block<T: NetworkEvent, P: PublicId> {
     payload: Observation<T, P>,
     proofs: BTreeSet<Proof<P>>,
+    // The consensus mode and expiry each voter signed along with the payload.
+    vote_terms: BTreeMap<P, (Option<ConsensusMode>, Option<usize>)>,
+    sequence_number: usize,
+    previous_hash: Option<BlockHash>,
+    consensus_mode: ConsensusMode,
 }
 
 impl<T: NetworkEvent, P: PublicId> Block<T, P> {
@@ -46,8 +55,37 @@ impl<T: NetworkEvent, P: PublicId> Block<T, P> {
             })
             .collect();
         let proofs = proofs?;
+        let vote_terms = votes
+            .iter()
+            .map(|(public_id, vote)| (public_id.clone(), (vote.consensus_mode(), vote.expiry())))
+            .collect();
 
-        Ok(Self { payload, proofs })
+        Ok(Self {
+            payload,
+            proofs,
+            vote_terms,
+            sequence_number: 0,
+            previous_hash: None,
+            consensus_mode: ConsensusMode::Supermajority,
+        })
+    }
+
+    // Places this block at `sequence_number` in the chain of the consensused blocks, following
+    // the block with the hash `previous_hash`.
+    pub(crate) fn in_chain(self, sequence_number: usize, previous_hash: Option<BlockHash>) -> Self {
+        Self {
+            sequence_number,
+            previous_hash,
+            ..self
+        }
+    }
+
+    // Records that the payload of this block was consensused according to `consensus_mode`.
+    pub(crate) fn with_consensus_mode(self, consensus_mode: ConsensusMode) -> Self {
+        Self {
+            consensus_mode,
+            ..self
+        }
     }
 
     /// Returns the payload of this block.
@@ -60,6 +98,57 @@ impl<T: NetworkEvent, P: PublicId> Block<T, P> {
         &self.proofs
     }
 
+    /// Returns the position of this block in the sequence of all the blocks consensused by the
+    /// section, starting from zero for the genesis block. A block created by `Block::new` is at
+    /// position zero.
+    ///
+    /// The position is that of the payload in the consensus history, so it is the same for all the
+    /// peers. In the rare case of a payload consensused without any vote by a voter, no block is
+    /// created for it and its position is skipped, the next block being chained to the previous
+    /// one created.
+    pub fn sequence_number(&self) -> usize {
+        self.sequence_number
+    }
+
+    /// Returns the hash of the block preceding this one in the chain of the consensused blocks, or
+    /// `None` for the genesis block and the blocks created by `Block::new`.
+    pub fn previous_hash(&self) -> Option<&BlockHash> {
+        self.previous_hash.as_ref()
+    }
+
+    /// Returns the consensus mode the payload of this block was consensused with. A block created
+    /// by `Block::new` has `ConsensusMode::Supermajority`.
+    ///
+    /// A voter can request a weaker mode than the section's for its vote for an `OpaquePayload`,
+    /// e.g. `ConsensusMode::Single`, so a block whose payload needs the agreement of the section
+    /// should be checked to have the expected mode.
+    pub fn consensus_mode(&self) -> ConsensusMode {
+        self.consensus_mode
+    }
+
+    /// Returns the hash of this block, to which the next block is chained. It covers the
+    /// position, the previous hash, the payload and the consensus mode of this block but not its
+    /// proofs, so it is the same for all the peers.
+    pub fn hash(&self) -> BlockHash {
+        BlockHash::new(
+            self.sequence_number,
+            self.previous_hash.as_ref(),
+            &ObservationHash::from(&self.payload),
+            self.consensus_mode,
+        )
+    }
+
+    /// Returns whether this block is chained to `previous`, i.e. follows it in the chain of the
+    /// consensused blocks.
+    ///
+    /// The voters sign their votes before the position of the payload in the chain is known, so
+    /// the proofs don't cover it: a block received from outside the section should be checked to
+    /// follow a block already trusted, as [LedgerReader](struct.LedgerReader.html) does.
+    pub fn follows(&self, previous: &Block<T, P>) -> bool {
+        self.previous_hash == Some(previous.hash())
+            && self.sequence_number > previous.sequence_number
+    }
+
     /// Is this block signed by the given peer?
     pub fn is_signed_by(&self, peer_id: &P) -> bool {
         self.proofs.iter().any(|proof| proof.public_id() == peer_id)
@@ -74,17 +163,172 @@ impl<T: NetworkEvent, P: PublicId> Block<T, P> {
             return Err(Error::MismatchedPayload);
         }
         let proof = vote.create_proof(peer_id)?;
+        let _ = self
+            .vote_terms
+            .insert(peer_id.clone(), (vote.consensus_mode(), vote.expiry()));
         Ok(self.proofs.insert(proof))
     }
+
+    // Returns whether `proof` is a valid signature of the payload of this block, by a voter who
+    // didn't request a consensus mode other than the one of this block.
+    pub(crate) fn is_valid_proof(&self, proof: &Proof<P>) -> bool {
+        let (consensus_mode, expiry) = if let Some(terms) = self.vote_terms.get(proof.public_id()) {
+            *terms
+        } else {
+            return false;
+        };
+        consensus_mode.map_or(true, |mode| mode.of(&self.payload) == self.consensus_mode)
+            && proof.is_valid(&vote::signed_data(&self.payload, consensus_mode, expiry))
+    }
+}
+
+/// Hash chaining a block to the blocks consensused before it.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct BlockHash(Hash);
+
+impl BlockHash {
+    pub(crate) fn new(
+        sequence_number: usize,
+        previous_hash: Option<&BlockHash>,
+        payload_hash: &ObservationHash,
+        consensus_mode: ConsensusMode,
+    ) -> Self {
+        BlockHash(Hash::from(
+            serialise(&(sequence_number, previous_hash, payload_hash, consensus_mode)).as_slice(),
+        ))
+    }
+}
+
+impl Debug for BlockHash {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        self.0.fmt(formatter)
+    }
+}
+
+/// A `Block` bundled with the hash of the voters which consensused it, so that anyone knowing those
+/// voters can trust the block without running Parsec, using [verify_block](fn.verify_block.html).
+///
+/// Only blocks consensused with `ConsensusMode::Supermajority` can be verified this way. The
+/// position of the block in the chain isn't signed by the voters, so it should be checked
+/// separately using [Block::follows](struct.Block.html#method.follows).
+#[serde(bound = "")]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct BlockCertificate<T: NetworkEvent, P: PublicId> {
+    block: Block<T, P>,
+    voters_hash: Hash,
+}
+
+impl<T: NetworkEvent, P: PublicId> BlockCertificate<T, P> {
+    /// Creates a certificate of `block`, consensused by `voters` holding the given voting
+    /// weights. These are the voters before any change the block itself makes to the section.
+    pub fn new(block: Block<T, P>, voters: &BTreeMap<P, u64>) -> Self {
+        Self {
+            block,
+            voters_hash: hash_voters(voters),
+        }
+    }
+
+    /// Returns the certified block.
+    pub fn block(&self) -> &Block<T, P> {
+        &self.block
+    }
+
+    /// Returns the signatures of the voters of the certified block.
+    pub fn proofs(&self) -> &BTreeSet<Proof<P>> {
+        self.block.proofs()
+    }
+
+    /// Returns whether this certificate was created for the given voters and voting weights.
+    pub fn is_for_voters(&self, voters: &BTreeMap<P, u64>) -> bool {
+        self.voters_hash == hash_voters(voters)
+    }
+
+    /// Consumes this certificate, returning the certified block.
+    pub fn into_block(self) -> Block<T, P> {
+        self.block
+    }
+}
+
+/// Verifies that the block of `certificate` was consensused by `voters`, holding the given voting
+/// weights: the certificate must have been created for these voters, and its block must carry
+/// valid signatures of its payload and consensus mode by voters holding more than two thirds of
+/// their total weight.
+///
+/// Returns `Error::MismatchedVoters` if the certificate was created for different voters, or
+/// `Error::InsufficientProofs` if the valid signatures are not enough.
+pub fn verify_block<T: NetworkEvent, P: PublicId>(
+    certificate: &BlockCertificate<T, P>,
+    voters: &BTreeMap<P, u64>,
+) -> Result<(), Error> {
+    if !certificate.is_for_voters(voters) {
+        return Err(Error::MismatchedVoters);
+    }
+
+    // Each voter counts once, however many valid proofs it has in the block.
+    let signers: Vec<_> = certificate
+        .proofs()
+        .iter()
+        .filter(|proof| certificate.block.is_valid_proof(proof))
+        .map(|proof| proof.public_id())
+        .collect();
+    let signed_weight = voters
+        .iter()
+        .filter(|(public_id, _)| signers.contains(public_id))
+        .map(|(_, weight)| u128::from(*weight))
+        .sum();
+    let total_weight = voters.values().map(|weight| u128::from(*weight)).sum();
+
+    if is_more_than_two_thirds_of_weight(signed_weight, total_weight) {
+        Ok(())
+    } else {
+        Err(Error::InsufficientProofs)
+    }
+}
+
+fn hash_voters<P: PublicId>(voters: &BTreeMap<P, u64>) -> Hash {
+    Hash::from(serialise(voters).as_slice())
+}
+
+/// Information about the meta-election in which a group of blocks was decided.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Debug)]
+pub struct ElectionInfo {
+    sequence_number: usize,
+    deciding_event: EventHash,
+}
+
+impl ElectionInfo {
+    pub(crate) fn new(sequence_number: usize, deciding_event: EventHash) -> Self {
+        Self {
+            sequence_number,
+            deciding_event,
+        }
+    }
+
+    /// Returns the position of the meta-election in the sequence of all the meta-elections
+    /// decided so far, starting from zero. All peers number the meta-elections the same way,
+    /// including the ones which didn't produce any block.
+    pub fn sequence_number(&self) -> usize {
+        self.sequence_number
+    }
+
+    /// Returns the hash of the gossip event at which the owning peer decided the meta-election.
+    /// Different peers might decide the same meta-election at different events.
+    pub fn deciding_event(&self) -> &EventHash {
+        &self.deciding_event
+    }
 }
 
 /// Group of blocks that were all created within the same meta-election.
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
-pub(crate) struct BlockGroup<T: NetworkEvent, P: PublicId>(pub VecDeque<Block<T, P>>);
+#[serde(bound = "")]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub(crate) struct BlockGroup<T: NetworkEvent, P: PublicId> {
+    pub blocks: VecDeque<Block<T, P>>,
+    pub election: ElectionInfo,
+}
 
 impl<T: NetworkEvent, P: PublicId> BlockGroup<T, P> {
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.blocks.is_empty()
     }
 }
 
@@ -93,7 +337,7 @@ impl<T: NetworkEvent, P: PublicId> IntoIterator for BlockGroup<T, P> {
     type IntoIter = vec_deque::IntoIter<Block<T, P>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+        self.blocks.into_iter()
     }
 }
 
@@ -102,19 +346,19 @@ impl<'a, T: NetworkEvent, P: PublicId> IntoIterator for &'a BlockGroup<T, P> {
     type IntoIter = vec_deque::Iter<'a, Block<T, P>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.iter()
+        self.blocks.iter()
     }
 }
 
 impl<T: NetworkEvent, P: PublicId> Deref for BlockGroup<T, P> {
     type Target = VecDeque<Block<T, P>>;
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.blocks
     }
 }
 
 impl<T: NetworkEvent, P: PublicId> DerefMut for BlockGroup<T, P> {
     fn deref_mut(&mut self) -> &mut VecDeque<Block<T, P>> {
-        &mut self.0
+        &mut self.blocks
     }
 }
@@ -0,0 +1,164 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    block::BlockHash,
+    error::Error,
+    gossip::EventHash,
+    hash::Hash,
+    id::{Proof, PublicId, SecretId},
+    observation::{is_more_than_two_thirds_of_weight, snapshot::ObservationKeySnapshot},
+    serialise,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The consensused state of a section following a stable block, from which a peer joining the
+/// section can start instead of receiving the whole gossip history.
+///
+/// Section members create it using
+/// [create_checkpoint](struct.Parsec.html#method.create_checkpoint). The checkpoints created by
+/// different members following the same block only differ in their signatures and frontiers,
+/// which can be gathered using [merge](#method.merge). Once signed by a supermajority of the
+/// voters, the joining peer can start from it using
+/// [from_checkpoint](struct.Parsec.html#method.from_checkpoint).
+#[serde(bound = "")]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct Checkpoint<P: PublicId> {
+    content: CheckpointContent<P>,
+    proofs: BTreeSet<Proof<P>>,
+    // Hashes of the events preceding the checkpoint in the graph of each signer which the events
+    // following it can have as parents. Each signer signs its frontier along with the content, so
+    // the joining peer only accepts these as the parents it never receives.
+    frontiers: BTreeMap<P, BTreeSet<EventHash>>,
+}
+
+#[serde(bound = "")]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub(crate) struct CheckpointContent<P: PublicId> {
+    // Number of meta-elections decided before the checkpoint.
+    pub decided_elections: usize,
+    // All the peers of the section, including the removed ones, with their voting weights.
+    pub peers: BTreeMap<P, u64>,
+    // Voters of the meta-election following the checkpoint.
+    pub voters: BTreeSet<P>,
+    // Keys of the consensused blocks' payloads in the order they were consensused.
+    pub consensus_history: Vec<ObservationKeySnapshot<P>>,
+    // Hash of the last consensused block, which the next block is chained to.
+    pub last_block_hash: Option<BlockHash>,
+    // Round hashes of all the peers for the first round of the meta-election following the
+    // checkpoint.
+    pub round_hashes: BTreeMap<P, Hash>,
+}
+
+impl<P: PublicId> Checkpoint<P> {
+    // Creates a checkpoint with the given content and our frontier, signed by `our_id`.
+    pub(crate) fn new<S: SecretId<PublicId = P>>(
+        content: CheckpointContent<P>,
+        frontier: BTreeSet<EventHash>,
+        our_id: &S,
+    ) -> Self {
+        let proof = Proof {
+            public_id: our_id.public_id().clone(),
+            signature: our_id.sign_detached(&serialise(&(&content, &frontier))),
+        };
+
+        Self {
+            content,
+            proofs: Some(proof).into_iter().collect(),
+            frontiers: Some((our_id.public_id().clone(), frontier))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Returns the number of blocks consensused before this checkpoint.
+    pub fn block_count(&self) -> usize {
+        self.content.consensus_history.len()
+    }
+
+    /// Returns the number of meta-elections decided before this checkpoint. It matches the
+    /// sequence number of the election of the first block consensused after it.
+    pub fn decided_elections(&self) -> usize {
+        self.content.decided_elections
+    }
+
+    /// Returns the voters of the section following this checkpoint.
+    pub fn voters(&self) -> &BTreeSet<P> {
+        &self.content.voters
+    }
+
+    /// Returns the proofs of this checkpoint.
+    pub fn proofs(&self) -> &BTreeSet<Proof<P>> {
+        &self.proofs
+    }
+
+    /// Adds the proofs and frontiers of `other` to this checkpoint. Returns an error if `other`
+    /// follows a different block or any of its proofs is invalid, in which case no proof is added.
+    pub fn merge(&mut self, other: Checkpoint<P>) -> Result<(), Error> {
+        if self.content != other.content {
+            return Err(Error::InvalidCheckpoint);
+        }
+
+        if !other.proofs.iter().all(|proof| other.is_valid(proof)) {
+            return Err(Error::SignatureFailure);
+        }
+
+        for proof in &other.proofs {
+            if let Some(frontier) = other.frontiers.get(proof.public_id()) {
+                let _ = self
+                    .frontiers
+                    .insert(proof.public_id().clone(), frontier.clone());
+            }
+        }
+        self.proofs.extend(other.proofs);
+        Ok(())
+    }
+
+    /// Returns whether this checkpoint holds valid signatures of voters holding more than two
+    /// thirds of the total weight of its voters.
+    pub fn is_signed_by_supermajority(&self) -> bool {
+        let weight_of =
+            |peer_id: &P| u128::from(self.content.peers.get(peer_id).cloned().unwrap_or(0));
+
+        let signers: BTreeSet<_> = self.valid_proofs().map(|proof| proof.public_id()).collect();
+        let signed_weight = signers.into_iter().map(weight_of).sum();
+        let total_weight = self.content.voters.iter().map(weight_of).sum();
+
+        is_more_than_two_thirds_of_weight(signed_weight, total_weight)
+    }
+
+    pub(crate) fn content(&self) -> &CheckpointContent<P> {
+        &self.content
+    }
+
+    // Returns the hashes of the events preceding the checkpoint which the voters which signed it
+    // committed to as the possible parents of the events following it.
+    pub(crate) fn frontier(&self) -> BTreeSet<EventHash> {
+        self.valid_proofs()
+            .filter_map(|proof| self.frontiers.get(proof.public_id()))
+            .flat_map(|frontier| frontier.iter().cloned())
+            .collect()
+    }
+
+    // Returns the valid proofs of the voters of the checkpoint.
+    fn valid_proofs(&self) -> impl Iterator<Item = &Proof<P>> {
+        self.proofs
+            .iter()
+            .filter(move |proof| self.content.voters.contains(proof.public_id()))
+            .filter(move |proof| self.is_valid(proof))
+    }
+
+    // Returns whether `proof` signs the content along with the frontier of its signer.
+    fn is_valid(&self, proof: &Proof<P>) -> bool {
+        self.frontiers
+            .get(proof.public_id())
+            .map_or(false, |frontier| {
+                proof.is_valid(&serialise(&(&self.content, frontier)))
+            })
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{hash::Hash, id::PublicId};
+use std::collections::BTreeMap;
+
+/// Source of the common coin tossed by the meta-elections when the meta-votes don't converge,
+/// registered using [set_common_coin](struct.Parsec.html#method.set_common_coin).
+///
+/// A coin is identified by a message which all the peers agree on. Every peer must compute the
+/// same value for the same message from the same gossip graph, so all the peers of a section have
+/// to use the same kind of coin.
+pub trait CommonCoin<P: PublicId> {
+    /// Returns the share of the coin identified by `message` contributed by the owning peer, or
+    /// `None` if this coin isn't built from shares. The returned share is published to the other
+    /// peers in a gossip event. Called at most once per message.
+    fn create_share(&mut self, _message: &[u8]) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Returns the value of the coin identified by `message`, or `None` if it can't be determined
+    /// yet. The value must only depend on `message` and on the data provided by `context`.
+    fn toss(&self, message: &[u8], context: &CoinContext<P>) -> Option<bool>;
+}
+
+/// Data from the gossip graph available to a [CommonCoin](trait.CommonCoin.html) when tossing it.
+pub struct CoinContext<'a, P: PublicId> {
+    leader_coin: &'a dyn Fn() -> Option<bool>,
+    shares: &'a dyn Fn() -> BTreeMap<P, Vec<u8>>,
+}
+
+impl<'a, P: PublicId> CoinContext<'a, P> {
+    pub(crate) fn new(
+        leader_coin: &'a dyn Fn() -> Option<bool>,
+        shares: &'a dyn Fn() -> BTreeMap<P, Vec<u8>>,
+    ) -> Self {
+        Self {
+            leader_coin,
+            shares,
+        }
+    }
+
+    /// Returns the least significant bit of the hash of the first event which carries a
+    /// meta-vote for the coin's round and step by the leader of the round, if the tossing event
+    /// can see it. If the tossing event has waited long enough for it, the next leaders are tried
+    /// in turn.
+    pub fn leader_coin(&self) -> Option<bool> {
+        (self.leader_coin)()
+    }
+
+    /// Returns the shares of the coin seen by the tossing event, by the voters which created them.
+    pub fn shares(&self) -> BTreeMap<P, Vec<u8>> {
+        (self.shares)()
+    }
+}
+
+/// The default coin, derived from the hash of an event by the leader of the round, as described in
+/// the PARSEC whitepaper.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct HashCoin;
+
+impl<P: PublicId> CommonCoin<P> for HashCoin {
+    fn toss(&self, _message: &[u8], context: &CoinContext<P>) -> Option<bool> {
+        context.leader_coin()
+    }
+}
+
+/// A threshold signature scheme with unique signatures, such as threshold BLS, where the key
+/// shares of the voters have been set up beforehand.
+pub trait ThresholdSigner<P: PublicId> {
+    /// Returns the owning peer's signature share of `message`.
+    fn sign_share(&self, message: &[u8]) -> Vec<u8>;
+
+    /// Returns whether `share` is a valid signature share of `message` by `peer_id`.
+    fn verify_share(&self, peer_id: &P, message: &[u8], share: &[u8]) -> bool;
+
+    /// Combines the valid `shares` of `message` into the signature of `message`, or returns `None`
+    /// if there aren't enough of them. The signature must be the same for any set of shares that
+    /// is large enough.
+    fn combine_shares(&self, message: &[u8], shares: &BTreeMap<P, Vec<u8>>) -> Option<Vec<u8>>;
+}
+
+/// A coin derived from the threshold signature of its message, which can't be predicted or biased
+/// as long as fewer peers than the threshold collude.
+#[derive(Clone, Debug)]
+pub struct ThresholdCoin<S> {
+    signer: S,
+}
+
+impl<S> ThresholdCoin<S> {
+    /// Creates a coin using `signer` to sign and combine the shares.
+    pub fn new(signer: S) -> Self {
+        Self { signer }
+    }
+}
+
+impl<P: PublicId, S: ThresholdSigner<P>> CommonCoin<P> for ThresholdCoin<S> {
+    fn create_share(&mut self, message: &[u8]) -> Option<Vec<u8>> {
+        Some(self.signer.sign_share(message))
+    }
+
+    fn toss(&self, message: &[u8], context: &CoinContext<P>) -> Option<bool> {
+        let shares = context
+            .shares()
+            .into_iter()
+            .filter(|(peer_id, share)| self.signer.verify_share(peer_id, message, share))
+            .collect();
+        let signature = self.signer.combine_shares(message, &shares)?;
+        Some(Hash::from(&signature[..]).least_significant_bit())
+    }
+}
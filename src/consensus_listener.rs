@@ -0,0 +1,33 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    block::{Block, ElectionInfo},
+    id::PublicId,
+    network_event::NetworkEvent,
+};
+use std::sync::mpsc::Sender;
+
+/// Receiver of the blocks consensused by a [Parsec](struct.Parsec.html) instance, registered using
+/// [set_consensus_listener](struct.Parsec.html#method.set_consensus_listener).
+pub trait ConsensusListener<T: NetworkEvent, P: PublicId> {
+    /// Called with the non-empty group of `blocks` decided in a single meta-election, in their
+    /// consensus order, as soon as the owning peer decides it. The groups are passed in the order
+    /// of their meta-elections.
+    fn handle_consensus(&mut self, blocks: Vec<Block<T, P>>, election: ElectionInfo);
+}
+
+/// Sends each group of blocks, together with the information about its meta-election, through the
+/// channel. Groups consensused after the receiving end has been dropped are discarded.
+impl<T: NetworkEvent, P: PublicId> ConsensusListener<T, P>
+    for Sender<(Vec<Block<T, P>>, ElectionInfo)>
+{
+    fn handle_consensus(&mut self, blocks: Vec<Block<T, P>>, election: ElectionInfo) {
+        let _ = self.send((blocks, election));
+    }
+}
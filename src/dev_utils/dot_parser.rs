@@ -11,6 +11,7 @@ use crate::error::Error;
 #[cfg(any(all(test, feature = "mock"), feature = "testing"))]
 use crate::gossip::EventContextRef;
 use crate::{
+    block::BlockHash,
     gossip::{CauseInput, Event, EventIndex, Graph, IndexedEventRef},
     hash::{Hash, HASH_LEN},
     meta_voting::{
@@ -193,7 +194,9 @@ fn parse_peers() -> Parser<u8, BTreeSet<PeerId>> {
 
 fn parse_consensus_mode() -> Parser<u8, ConsensusMode> {
     let parser = seq(b"Single").map(|_| ConsensusMode::Single)
-        | seq(b"Supermajority").map(|_| ConsensusMode::Supermajority);
+        | seq(b"Supermajority").map(|_| ConsensusMode::Supermajority)
+        | (seq(b"Threshold(") * parse_usize() - sym(b')'))
+            .map(|percent| ConsensusMode::Threshold(percent as u8));
     let parser = comment_prefix() * seq(b"consensus_mode: ") * parser - next_line();
     parser
         .opt()
@@ -562,7 +565,10 @@ fn parse_invalid() -> Parser<u8, MaliceInput> {
 }
 
 fn parse_genesis() -> Parser<u8, Observation<Transaction, PeerId>> {
-    (seq(b"Genesis(") * parse_peers() - seq(b")")).map(Observation::Genesis)
+    (seq(b"Genesis(") * parse_peers() - seq(b")")).map(|group| Observation::Genesis {
+        group,
+        weights: BTreeMap::new(),
+    })
 }
 
 fn parse_add() -> Parser<u8, Observation<Transaction, PeerId>> {
@@ -570,6 +576,7 @@ fn parse_add() -> Parser<u8, Observation<Transaction, PeerId>> {
         * parse_add_or_remove().map(|(peer_id, related_info)| Observation::Add {
             peer_id,
             related_info,
+            weight: 1,
         })
 }
 
@@ -1015,16 +1022,36 @@ fn convert_to_meta_election(
         indices_by_key: unconsensused_events_keyed_indices,
     };
 
-    MetaElection {
+    let mut converted = MetaElection {
         meta_events,
         round_hashes: convert_peer_id_map(meta_election.round_hashes, peer_list),
         voters: convert_peer_id_set(meta_election.voters, peer_list),
         interesting_events,
         unconsensused_events,
-        consensus_history: meta_election.consensus_history,
+        consensus_history: vec![],
+        last_block_hash: None,
+        // Not recorded in the dot files.
+        decided_elections: 0,
         continue_consensus_start_index: 0,
         new_consensus_start_index: 0,
-    }
+        joint_consensus: false,
+        previous_voters: None,
+    };
+    // The block hashes aren't recorded either, but follow from the consensus history provided
+    // each of its payloads got a block.
+    converted.last_block_hash = meta_election.consensus_history.iter().enumerate().fold(
+        None,
+        |previous_hash, (sequence_number, key)| {
+            Some(BlockHash::new(
+                sequence_number,
+                previous_hash.as_ref(),
+                key.hash(),
+                key.consensus_mode(),
+            ))
+        },
+    );
+    converted.consensus_history = meta_election.consensus_history;
+    converted
 }
 
 fn convert_to_meta_event(meta_event: ParsedMetaEvent, peer_list: &PeerList<PeerId>) -> MetaEvent {
@@ -43,6 +43,7 @@ pub struct Network {
     genesis: BTreeSet<PeerId>,
     msg_queue: BTreeMap<PeerId, Vec<QueueEntry>>,
     consensus_mode: ConsensusMode,
+    joint_consensus: bool,
 }
 
 #[derive(Debug)]
@@ -114,6 +115,7 @@ impl Network {
             genesis: BTreeSet::new(),
             msg_queue: BTreeMap::new(),
             consensus_mode,
+            joint_consensus: false,
         }
     }
 
@@ -121,6 +123,12 @@ impl Network {
         self.consensus_mode
     }
 
+    /// Makes the peers go through a joint-consensus transition whenever the voters change. Has to
+    /// be called before executing the schedule.
+    pub fn set_joint_consensus(&mut self, enabled: bool) {
+        self.joint_consensus = enabled;
+    }
+
     fn active_peers(&self) -> impl Iterator<Item = &Peer> {
         self.peers
             .values()
@@ -408,6 +416,9 @@ impl Network {
             ConsensusMode::Supermajority => {
                 is_more_than_two_thirds(signatories.len(), section.len())
             }
+            ConsensusMode::Threshold(percent) => {
+                100 * signatories.len() > usize::from(percent) * section.len()
+            }
         };
         if !correct_signatories {
             return Err(ConsensusError::TooFewSignatures {
@@ -425,7 +436,7 @@ impl Network {
 
         for block_group in block_groups {
             for block in block_group {
-                if let ParsecObservation::Genesis(ref g) = *block.payload() {
+                if let ParsecObservation::Genesis { group: ref g, .. } = *block.payload() {
                     valid_voters = g.clone();
                 }
 
@@ -434,13 +445,21 @@ impl Network {
 
             for block in block_group {
                 match *block.payload() {
-                    ParsecObservation::Genesis(_) => (),
+                    ParsecObservation::Genesis { .. } => (),
                     ParsecObservation::Add { ref peer_id, .. } => {
                         let _ = valid_voters.insert(peer_id.clone());
                     }
                     ParsecObservation::Remove { ref peer_id, .. } => {
                         let _ = valid_voters.remove(peer_id);
                     }
+                    ParsecObservation::Reconfigure {
+                        ref add,
+                        ref remove,
+                        ..
+                    } => {
+                        valid_voters.extend(add.keys().cloned());
+                        valid_voters = &valid_voters - remove;
+                    }
                     _ => {}
                 }
             }
@@ -537,9 +556,13 @@ impl Network {
                     Peer::malicious_from_genesis(id.clone(), &genesis_ids, self.consensus_mode)
                 });
 
+                let joint_consensus = self.joint_consensus;
                 self.peers = good_peers
                     .chain(malicious_peers)
-                    .map(|peer| (peer.id().clone(), peer))
+                    .map(|mut peer| {
+                        peer.set_joint_consensus(joint_consensus);
+                        (peer.id().clone(), peer)
+                    })
                     .collect();
 
                 if let Some(keep_consensus) = &options.genesis_restrict_consensus_to {
@@ -562,15 +585,14 @@ impl Network {
                     return Ok(false);
                 }
                 let current_peers = self.active_peers().map(|peer| peer.id().clone()).collect();
-                let _ = self.peers.insert(
+                let mut peer = Peer::from_existing(
                     peer_id.clone(),
-                    Peer::from_existing(
-                        peer_id.clone(),
-                        &self.genesis,
-                        &current_peers,
-                        self.consensus_mode,
-                    ),
+                    &self.genesis,
+                    &current_peers,
+                    self.consensus_mode,
                 );
+                peer.set_joint_consensus(self.joint_consensus);
+                let _ = self.peers.insert(peer_id.clone(), peer);
             }
             ScheduleEvent::RemovePeer(peer_id) => {
                 if self.allow_removal_of_peer(&peer_id) {
@@ -629,6 +651,22 @@ impl Network {
                             return Ok(false);
                         }
                     }
+                    ParsecObservation::Reconfigure {
+                        ref add,
+                        ref remove,
+                        ..
+                    } => {
+                        if add.keys().any(|peer_id| !self.peers.contains_key(peer_id))
+                            || !remove
+                                .iter()
+                                .all(|peer_id| self.allow_removal_of_peer(peer_id))
+                        {
+                            return Ok(false);
+                        }
+                        for peer_id in remove {
+                            (*self.peer_mut(peer_id)).mark_network_view_as_leaving();
+                        }
+                    }
                     _ => (),
                 }
 
@@ -646,11 +684,32 @@ impl Network {
                 let leaving_count = self.num_with_network_view(NetworkView::Leaving);
                 let current_count = joined_count + leaving_count;
                 is_more_than_two_thirds(joined_count - 1, current_count)
+                    && self.allow_removal_during_joint_consensus_transition(peer_id)
             }
             Some(NetworkView::Leaving) | Some(NetworkView::Left) => true,
         }
     }
 
+    // In joint-consensus mode, a transition meta-election also needs a supermajority of the
+    // previous voters, some of which may have left already. Returns true if the ones remaining
+    // joined still form a supermajority of them without `peer_id`.
+    fn allow_removal_during_joint_consensus_transition(&self, peer_id: &PeerId) -> bool {
+        self.peers
+            .values()
+            .filter(|peer| peer.is_running() && peer.network_view() == NetworkView::Joined)
+            .filter_map(Peer::previous_voters)
+            .all(|previous_voters| {
+                let joined_count = previous_voters
+                    .iter()
+                    .filter(|id| *id != peer_id)
+                    .filter(|id| {
+                        self.peers.get(id).map(Peer::network_view) == Some(NetworkView::Joined)
+                    })
+                    .count();
+                is_more_than_two_thirds(joined_count, previous_voters.len())
+            })
+    }
+
     fn allow_addition_of_peer(&self) -> bool {
         // For sections of size 3 or more, we only allow new node to join if the currently joined
         // ones would still form a supermajority event after all the joining one including the new
@@ -311,6 +311,15 @@ impl Peer {
         }
     }
 
+    pub fn set_joint_consensus(&mut self, enabled: bool) {
+        self.parsec.set_joint_consensus(enabled);
+    }
+
+    /// Returns the previous voters if the current meta-election is a joint-consensus transition.
+    pub fn previous_voters(&self) -> Option<Vec<PeerId>> {
+        self.parsec.previous_voters()
+    }
+
     pub fn vote_for(&mut self, observation: &Observation) {
         self.votes_to_make.push(observation.clone());
     }
@@ -347,10 +356,13 @@ impl Peer {
 
     fn make_active_if_added(&mut self, block: &Block<Transaction, PeerId>) {
         if self.status == PeerStatus::Pending {
-            if let ParsecObservation::Add { ref peer_id, .. } = *block.payload() {
-                if self.id() == peer_id {
-                    self.status = PeerStatus::Active;
-                }
+            let added = match *block.payload() {
+                ParsecObservation::Add { ref peer_id, .. } => self.id() == peer_id,
+                ParsecObservation::Reconfigure { ref add, .. } => add.contains_key(self.id()),
+                _ => false,
+            };
+            if added {
+                self.status = PeerStatus::Active;
             }
         }
     }
@@ -368,6 +380,14 @@ impl Peer {
                     ParsecObservation::Remove { peer_id, .. } => {
                         assert!(self.removed_peers_ids.insert(peer_id.clone()))
                     }
+                    ParsecObservation::Reconfigure { add, remove, .. } => {
+                        for peer_id in add.keys().filter(|peer_id| !remove.contains(peer_id)) {
+                            assert!(self.added_peers_ids.insert(peer_id.clone()))
+                        }
+                        for peer_id in remove {
+                            assert!(self.removed_peers_ids.insert(peer_id.clone()))
+                        }
+                    }
                     _ => (),
                 }
             }
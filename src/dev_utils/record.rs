@@ -10,6 +10,7 @@ use super::dot_parser::{parse_dot_file, ParsedContents};
 use crate::{
     gossip::{Cause, Event, IndexedEventRef, PackedEvent, Request, Response},
     hash::Hash,
+    journal::JournalEntry,
     mock::{PeerId, Transaction},
     observation::{ConsensusMode, Observation, ObservationKey, ObservationStore},
     parsec::Parsec,
@@ -24,7 +25,7 @@ use std::{collections::BTreeSet, io, path::Path};
 pub struct Record {
     our_id: PeerId,
     genesis_group: BTreeSet<PeerId>,
-    actions: Vec<Action>,
+    actions: Vec<JournalEntry<Transaction, PeerId>>,
     // Keys of the consensused blocks' payloads in the order they were consensused.
     consensus_history: Vec<ObservationKey>,
     // Consensus mode to play
@@ -45,7 +46,7 @@ impl Record {
             Parsec::from_genesis(self.our_id, &self.genesis_group, self.consensus_mode);
 
         for action in self.actions {
-            action.run(&mut parsec)
+            unwrap!(parsec.handle_journal_entry(action))
         }
 
         parsec
@@ -112,7 +113,7 @@ impl From<ParsedContents> for Record {
                         }
                     }
 
-                    actions.push(Action::Vote(observation.clone()));
+                    actions.push(JournalEntry::Vote(observation.clone()));
                 } else if event.is_request() || event.is_response() {
                     known[event.topological_index()] = true;
 
@@ -133,9 +134,9 @@ impl From<ParsedContents> for Record {
                         collect_events_to_gossip(&contents, other_parent, &mut known);
 
                     if event.is_request() {
-                        actions.push(Action::Request(src, Request::new(events_to_gossip)))
+                        actions.push(JournalEntry::Request(src, Request::new(events_to_gossip)))
                     } else {
-                        actions.push(Action::Response(src, Response::new(events_to_gossip)))
+                        actions.push(JournalEntry::Response(src, Response::new(events_to_gossip)))
                     }
 
                     // Skip all accusations directly following our sync event, as they will be
@@ -149,7 +150,7 @@ impl From<ParsedContents> for Record {
                         }
                         _ => unreachable!(),
                     };
-                    actions.push(Action::Requesting(recipient_id));
+                    actions.push(JournalEntry::CreateGossip(recipient_id));
                     skip_our_accusations = true;
                 } else {
                     panic!("Unexpected event {:?}", *event);
@@ -167,7 +168,7 @@ impl From<ParsedContents> for Record {
             let requesting_event =
                 PackedEvent::new_requesting(src.clone(), contents.our_id.clone(), self_parent);
             events_to_gossip.push(requesting_event);
-            actions.push(Action::Request(src, Request::new(events_to_gossip)));
+            actions.push(JournalEntry::Request(src, Request::new(events_to_gossip)));
         }
 
         Record {
@@ -181,29 +182,6 @@ impl From<ParsedContents> for Record {
     }
 }
 
-#[derive(Clone)]
-enum Action {
-    Vote(Observation<Transaction, PeerId>),
-    Requesting(PeerId),
-    Request(PeerId, Request<Transaction, PeerId>),
-    Response(PeerId, Response<Transaction, PeerId>),
-}
-
-impl Action {
-    fn run(self, parsec: &mut Parsec<Transaction, PeerId>) {
-        match self {
-            Action::Vote(observation) => unwrap!(parsec.vote_for(observation)),
-            Action::Requesting(recipient) => {
-                let _ = unwrap!(parsec.create_gossip(&recipient));
-            }
-            Action::Request(src, request) => {
-                let _ = unwrap!(parsec.handle_request(&src, request));
-            }
-            Action::Response(src, response) => unwrap!(parsec.handle_response(&src, response)),
-        }
-    }
-}
-
 fn extract_genesis_group<'a>(
     event: &Event<PeerId>,
     observations: &'a ObservationStore<Transaction, PeerId>,
@@ -213,7 +191,11 @@ fn extract_genesis_group<'a>(
         .and_then(|key| observations.get(key))
         .map(|info| &info.observation)
         .and_then(|observation| {
-            if let Observation::Genesis(ref genesis_group) = *observation {
+            if let Observation::Genesis {
+                group: ref genesis_group,
+                ..
+            } = *observation
+            {
                 Some(genesis_group)
             } else {
                 None
@@ -581,7 +581,7 @@ impl Schedule {
             let opaque_transactions = obs_schedule.extract_opaque();
             let sampling = match env.network.consensus_mode() {
                 ConsensusMode::Single => Sampling::Constant(1),
-                ConsensusMode::Supermajority => options.opaque_voters,
+                ConsensusMode::Supermajority | ConsensusMode::Threshold(_) => options.opaque_voters,
             };
 
             for obs in opaque_transactions {
@@ -609,6 +609,7 @@ impl Schedule {
                         let observation = ParsecObservation::Add {
                             peer_id: new_peer.clone(),
                             related_info: vec![],
+                            weight: 1,
                         };
 
                         peers.add_peer(new_peer.clone());
@@ -672,7 +673,9 @@ impl Schedule {
                         let observation = ParsecObservation::OpaquePayload(payload);
                         let sampling = match env.network.consensus_mode() {
                             ConsensusMode::Single => Sampling::Constant(1),
-                            ConsensusMode::Supermajority => options.opaque_voters,
+                            ConsensusMode::Supermajority | ConsensusMode::Threshold(_) => {
+                                options.opaque_voters
+                            }
                         };
 
                         pending.peers_make_observation(
@@ -470,7 +470,7 @@ mod detail {
 
         fn calculate_positions(&self) -> Vec<usize> {
             let mut positions = Vec::new();
-            positions.resize(self.gossip_graph.len(), 0);
+            positions.resize(self.gossip_graph.end_index(), 0);
 
             // gossip_graph in topological order: parent processed before children:
             for event in self.gossip_graph.iter() {
@@ -660,6 +660,7 @@ mod detail {
                     }
                 }
                 Cause::Initial => "Initial",
+                Cause::CoinShare { .. } => "CoinShare",
             };
 
             writeln!(&mut self.file, "/// cause: {}", cause)
@@ -1010,7 +1011,7 @@ mod detail {
             short_peer_ids: &PeerIndexMap<String>,
         ) -> Self {
             let value = match observation {
-                Observation::Genesis(group) => format!(
+                Observation::Genesis { group, .. } => format!(
                     "Genesis({:?})",
                     group.iter().map(sanitise_peer_id).collect::<BTreeSet<_>>()
                 ),
@@ -1018,6 +1019,16 @@ mod detail {
                 Observation::Remove { peer_id, .. } => {
                     format!("Remove({:?})", sanitise_peer_id(peer_id))
                 }
+                Observation::Rekey { old, new, .. } => format!(
+                    "Rekey({:?}, {:?})",
+                    sanitise_peer_id(old),
+                    sanitise_peer_id(new)
+                ),
+                Observation::Reconfigure { add, remove, .. } => format!(
+                    "Reconfigure({:?}, {:?})",
+                    add.keys().map(sanitise_peer_id).collect::<BTreeSet<_>>(),
+                    remove.iter().map(sanitise_peer_id).collect::<BTreeSet<_>>()
+                ),
                 Observation::Accusation { offender, malice } => format!(
                     "Accusation {{ {:?}, {} }}",
                     sanitise_peer_id(offender),
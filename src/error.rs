@@ -9,7 +9,7 @@
 use crate::peer_list::PeerState;
 use std::{
     fmt::{self, Display, Formatter},
-    result,
+    io, result,
 };
 
 /// Parsec error
@@ -53,6 +53,32 @@ pub enum Error {
     InvalidMessage,
     /// The request or response has already been handled by us.
     DuplicateMessage,
+    /// The snapshot is malformed or was taken by a different peer.
+    InvalidSnapshot,
+    /// The snapshot was written in a format version this node doesn't support.
+    UnsupportedSnapshotVersion(u32),
+    /// The checkpoint is malformed, follows a different block, or isn't signed by a supermajority
+    /// of its voters.
+    InvalidCheckpoint,
+    /// The block certificate was created for a different set of voters.
+    MismatchedVoters,
+    /// The block isn't signed by voters holding more than two thirds of the voting weight.
+    InsufficientProofs,
+    /// The observation gives a peer a voting weight of zero or above `MAX_WEIGHT`.
+    InvalidWeight,
+    /// The vote requests a consensus threshold of 100% or more.
+    InvalidConsensusMode,
+    /// The vote has a time-to-live of zero blocks, so it would never count.
+    InvalidTtl,
+    /// The ledger record is truncated or malformed.
+    InvalidLedger,
+    /// The block isn't chained to the block preceding it in the ledger.
+    BrokenChain,
+    /// Reading or writing the ledger failed.
+    Io(io::ErrorKind),
+    /// The request or response was written in a gossip protocol version this node doesn't
+    /// support.
+    IncompatibleVersion(u32),
     /// Logic error.
     Logic,
 }
@@ -98,6 +124,56 @@ impl Display for Error {
             ),
             Error::InvalidMessage => write!(f, "This non-empty message is invalid."),
             Error::DuplicateMessage => write!(f, "This message has already been handled."),
+            Error::InvalidSnapshot => write!(
+                f,
+                "The snapshot is malformed or was taken by a different peer."
+            ),
+            Error::UnsupportedSnapshotVersion(version) => {
+                write!(
+                    f,
+                    "The snapshot format version {} is not supported.",
+                    version
+                )
+            }
+            Error::InvalidCheckpoint => write!(
+                f,
+                "The checkpoint is malformed, follows a different block, or isn't signed by a \
+                 supermajority of its voters."
+            ),
+            Error::MismatchedVoters => write!(
+                f,
+                "The block certificate was created for a different set of voters."
+            ),
+            Error::InsufficientProofs => write!(
+                f,
+                "The block isn't signed by voters holding more than two thirds of the voting \
+                 weight."
+            ),
+            Error::InvalidWeight => write!(
+                f,
+                "The observation gives a peer a voting weight of zero or above MAX_WEIGHT."
+            ),
+            Error::InvalidConsensusMode => {
+                write!(
+                    f,
+                    "The vote requests a consensus threshold of 100% or more."
+                )
+            }
+            Error::InvalidTtl => write!(
+                f,
+                "The vote has a time-to-live of zero blocks, so it would never count."
+            ),
+            Error::InvalidLedger => write!(f, "The ledger record is truncated or malformed."),
+            Error::BrokenChain => write!(
+                f,
+                "The block isn't chained to the block preceding it in the ledger."
+            ),
+            Error::Io(kind) => write!(f, "Reading or writing the ledger failed ({:?}).", kind),
+            Error::IncompatibleVersion(version) => write!(
+                f,
+                "The gossip protocol version {} is not supported.",
+                version
+            ),
             Error::Logic => write!(
                 f,
                 "This is a logic error and represents a flaw in the code."
@@ -106,5 +182,11 @@ impl Display for Error {
     }
 }
 
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error.kind())
+    }
+}
+
 /// A specialised `Result` type for Parsec.
 pub type Result<T> = result::Result<T, Error>;
@@ -7,18 +7,33 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    block::Block,
+    block::{verify_block, Block, BlockCertificate},
+    common_coin::{ThresholdCoin, ThresholdSigner},
     dev_utils::{parse_test_dot_file, Record, TestIterator},
     error::Error,
-    gossip::{Event, Graph, GraphSnapshot},
+    gossip::{
+        Event, Fetch, FetchResponse, GossipStrategy, Graph, GraphSnapshot, PackedEvent, Request,
+        Response, PROTOCOL_VERSION,
+    },
     id::{Proof, PublicId},
+    journal::{Journal, JournalEntry},
     meta_voting::MetaElectionSnapshot,
     mock::{self, PeerId, Transaction},
-    observation::{ConsensusMode, Observation},
-    parsec::TestParsec,
+    network_event::NetworkEvent,
+    observation::{ConsensusMode, Observation, MAX_WEIGHT},
+    parsec::{TestParsec, MAX_FETCHED_HASHES, MAX_ORPHAN_EVENTS, SNAPSHOT_VERSION},
     peer_list::{PeerListSnapshot, PeerState},
+    serialise,
+    vote::Vote,
+};
+use maidsafe_utilities::serialisation;
+use rand::{Rng, SeedableRng, XorShiftRng};
+use std::{
+    cmp,
+    collections::{BTreeMap, BTreeSet},
+    iter,
+    sync::{mpsc, Arc, Mutex},
 };
-use std::collections::BTreeSet;
 
 type TestPeer = TestParsec<Transaction, PeerId>;
 
@@ -49,6 +64,53 @@ fn nth_event<P: PublicId>(graph: &Graph<P>, n: usize) -> &Event<P> {
     unwrap!(graph.iter_from(n).next()).inner()
 }
 
+// Creates an instance for each of the first `count` mock peers, with all of them as genesis group.
+fn create_peers<T: NetworkEvent>(count: usize) -> Vec<TestParsec<T, PeerId>> {
+    create_peers_with_mode(count, ConsensusMode::Supermajority)
+}
+
+fn create_peers_with_mode<T: NetworkEvent>(
+    count: usize,
+    consensus_mode: ConsensusMode,
+) -> Vec<TestParsec<T, PeerId>> {
+    let ids = mock::create_ids(count);
+    let genesis_group: BTreeSet<_> = ids.iter().cloned().collect();
+    ids.into_iter()
+        .map(|id| TestParsec::from_genesis(id, &genesis_group, consensus_mode))
+        .collect()
+}
+
+// Has peer `src` send a gossip request to peer `dst`, and handle its response.
+fn exchange_gossip<T: NetworkEvent>(peers: &mut [TestParsec<T, PeerId>], src: usize, dst: usize) {
+    let src_id = peers[src].our_pub_id().clone();
+    let dst_id = peers[dst].our_pub_id().clone();
+    let request = unwrap!(peers[src].create_gossip(&dst_id));
+    let response = unwrap!(peers[dst].handle_request(&src_id, request));
+    unwrap!(peers[src].handle_response(&dst_id, response));
+}
+
+// Has every peer exchange gossip with another one picked at random.
+fn gossip_round<T: NetworkEvent>(peers: &mut [TestParsec<T, PeerId>], rng: &mut XorShiftRng) {
+    for src in 0..peers.len() {
+        let dst = (src + rng.gen_range(1, peers.len())) % peers.len();
+        exchange_gossip(peers, src, dst);
+    }
+}
+
+// Gossips in rounds until all the peers have consensused at least `count` blocks.
+fn gossip_until_blocks(peers: &mut [TestPeer], rng: &mut XorShiftRng, count: usize) {
+    let mut round = 0;
+    while peers
+        .iter()
+        .any(|peer| peer.consensused_blocks().count() < count)
+    {
+        round += 1;
+        assert!(round < 100, "Consensus not reached.");
+
+        gossip_round(peers, rng);
+    }
+}
+
 #[test]
 fn from_existing() {
     let mut peers = mock::create_ids(10);
@@ -161,7 +223,13 @@ fn from_genesis() {
     assert_eq!(*parsec.event_creator_id(&genesis_observation), our_id);
     match parsec.event_payload(&genesis_observation) {
         Some(payload) => {
-            assert_eq!(*payload, Observation::Genesis(peers));
+            assert_eq!(
+                *payload,
+                Observation::Genesis {
+                    group: peers,
+                    weights: BTreeMap::new(),
+                }
+            );
         }
         None => panic!("Expected observation, but event carried no vote"),
     }
@@ -340,6 +408,7 @@ fn unpolled_and_unconsensused_observations() {
     let add_eric = Observation::Add {
         peer_id: PeerId::new("Eric"),
         related_info: vec![],
+        weight: 1,
     };
     assert_eq!(alice.our_unpolled_observations().count(), 1);
     assert_eq!(*unwrap!(alice.our_unpolled_observations().next()), add_eric);
@@ -390,7 +459,7 @@ fn our_unpolled_observations_with_consensus_mode_single() {
     let mut alice = Record::from(parse_test_dot_file("alice.dot")).play();
 
     let block = unwrap!(alice.poll());
-    if let Observation::Genesis(_) = block.payload() {
+    if let Observation::Genesis { .. } = block.payload() {
     } else {
         panic!();
     }
@@ -507,6 +576,1442 @@ fn sees() {
     assert!(!a3.sees(c2_1));
 }
 
+#[test]
+fn prune() {
+    // Run the same network twice, once with and once without pruning.
+    fn run(prune: bool) -> (Vec<TestPeer>, Vec<Vec<Observation<Transaction, PeerId>>>) {
+        let mut peers: Vec<TestPeer> = create_peers(4);
+        let mut payloads = vec![vec![]; peers.len()];
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+
+        for round in 0..40 {
+            if round % 5 == 0 && round < 20 {
+                let vote = Observation::OpaquePayload(Transaction::new(format!("{}", round)));
+                for peer in &mut peers {
+                    unwrap!(peer.vote_for(vote.clone()));
+                }
+            }
+
+            gossip_round(&mut peers, &mut rng);
+
+            for (peer, peer_payloads) in peers.iter_mut().zip(&mut payloads) {
+                while let Some(block) = peer.poll() {
+                    peer_payloads.push(block.payload().clone());
+                }
+                if prune {
+                    let _ = peer.prune();
+                }
+            }
+        }
+
+        (peers, payloads)
+    }
+
+    let (pruned_peers, pruned_payloads) = run(true);
+    let (peers, payloads) = run(false);
+
+    // Pruning made no difference to consensus...
+    assert!(payloads[0].len() > 2);
+    assert!(payloads
+        .iter()
+        .all(|peer_payloads| *peer_payloads == payloads[0]));
+    assert_eq!(pruned_payloads, payloads);
+
+    // ...and the pruned graphs are what remains of the full ones after discarding their oldest
+    // events.
+    for (pruned_peer, peer) in pruned_peers.iter().zip(&peers) {
+        let start_index = pruned_peer.graph().start_index();
+        assert!(start_index > 0);
+        assert_eq!(pruned_peer.graph().len(), peer.graph().len() - start_index);
+        assert!(pruned_peer
+            .graph()
+            .iter()
+            .zip(peer.graph().iter_from(start_index))
+            .all(|(lhs, rhs)| lhs.hash() == rhs.hash()));
+        assert!(pruned_peer
+            .graph()
+            .get_index(unwrap!(peer.graph().iter().next()).hash())
+            .is_none());
+    }
+}
+
+#[test]
+fn snapshot_and_restore() {
+    let ids = mock::create_ids(4);
+    let mut peers: Vec<TestPeer> = create_peers(ids.len());
+    let mut payloads = vec![vec![]; peers.len()];
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+
+    for round in 0..40 {
+        if round % 5 == 0 && round < 20 {
+            let vote = Observation::OpaquePayload(Transaction::new(format!("{}", round)));
+            for peer in &mut peers {
+                unwrap!(peer.vote_for(vote.clone()));
+            }
+        }
+
+        // Restart the first peer from its snapshot, leaving some of its blocks unpolled.
+        if round == 10 {
+            let bytes = peers[0].snapshot();
+            let restored = unwrap!(TestParsec::restore(ids[0].clone(), &bytes));
+            assert_eq!(Snapshot::new(&restored), Snapshot::new(&peers[0]));
+            assert_eq!(restored.graph(), peers[0].graph());
+            peers[0] = restored;
+        }
+
+        gossip_round(&mut peers, &mut rng);
+
+        if round % 2 == 0 {
+            for (peer, peer_payloads) in peers.iter_mut().zip(&mut payloads) {
+                while let Some(block) = peer.poll() {
+                    peer_payloads.push(block.payload().clone());
+                }
+            }
+        }
+    }
+
+    // The restored peer didn't fork and got the same blocks as everyone else.
+    assert!(payloads[0].len() > 2);
+    assert!(payloads
+        .iter()
+        .all(|peer_payloads| *peer_payloads == payloads[0]));
+    for peer in &peers {
+        let creator_events: Vec<_> = peer
+            .graph()
+            .iter()
+            .filter(|event| event.creator() == unwrap!(peer.peer_list().get_index(&ids[0])))
+            .map(|event| event.index_by_creator())
+            .collect();
+        assert!(creator_events.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    // A snapshot can't be restored by a different peer or from unsupported or corrupted bytes.
+    let bytes = peers[0].snapshot();
+    assert_eq!(
+        TestParsec::<Transaction, _>::restore(ids[1].clone(), &bytes).err(),
+        Some(Error::InvalidSnapshot)
+    );
+    assert_eq!(
+        TestParsec::<Transaction, _>::restore(ids[0].clone(), &bytes[..bytes.len() / 2]).err(),
+        Some(Error::InvalidSnapshot)
+    );
+    let mut bytes = bytes;
+    bytes[0] = bytes[0].wrapping_add(1);
+    assert_eq!(
+        TestParsec::<Transaction, _>::restore(ids[0].clone(), &bytes).err(),
+        Some(Error::UnsupportedSnapshotVersion(SNAPSHOT_VERSION + 1))
+    );
+}
+
+#[test]
+fn journal_replay() {
+    #[derive(Clone, Default)]
+    struct SharedJournal(Arc<Mutex<Vec<JournalEntry<Transaction, PeerId>>>>);
+
+    impl Journal<Transaction, PeerId> for SharedJournal {
+        fn append(&mut self, entry: &JournalEntry<Transaction, PeerId>) {
+            unwrap!(self.0.lock()).push(entry.clone())
+        }
+    }
+
+    let ids = mock::create_ids(4);
+    let mut peers: Vec<TestPeer> = create_peers(ids.len());
+    let journal = SharedJournal::default();
+    peers[0].set_journal(journal.clone());
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+
+    for round in 0..20 {
+        if round % 5 == 0 {
+            let vote = Observation::OpaquePayload(Transaction::new(format!("{}", round)));
+            for peer in &mut peers {
+                unwrap!(peer.vote_for(vote.clone()));
+            }
+        }
+
+        gossip_round(&mut peers, &mut rng);
+
+        // Pruning and creating checkpoints change the state as well.
+        if round == 10 {
+            let _ = unwrap!(peers[0].create_checkpoint());
+        }
+        let _ = peers[0].prune();
+    }
+
+    // Inputs which fail are journalled too, and fail again on replay.
+    assert_eq!(
+        peers[0].vote_for(Observation::OpaquePayload(Transaction::new("0"))),
+        Err(Error::DuplicateVote)
+    );
+
+    let entries = unwrap!(journal.0.lock()).clone();
+    assert!(entries.len() > 40);
+
+    // Replaying the journal rebuilds the same state without journalling the entries again.
+    let genesis_group: BTreeSet<_> = ids.iter().cloned().collect();
+    let mut replayed =
+        TestParsec::from_genesis(ids[0].clone(), &genesis_group, ConsensusMode::Supermajority);
+    replayed.set_journal(journal.clone());
+    replayed.replay(entries.clone());
+
+    assert_eq!(Snapshot::new(&replayed), Snapshot::new(&peers[0]));
+    assert_eq!(replayed.graph(), peers[0].graph());
+    assert_eq!(*unwrap!(journal.0.lock()), entries);
+    assert!(peers[0].consensused_blocks().next().is_some());
+}
+
+#[test]
+fn bounded_gossip() {
+    // Run the same network twice, once with and once without limiting the gossip size.
+    fn run(max_gossip_size: Option<usize>) -> (usize, Vec<Vec<Observation<Transaction, PeerId>>>) {
+        let mut peers: Vec<TestPeer> = create_peers(4);
+        for peer in &mut peers {
+            peer.set_max_gossip_size(max_gossip_size);
+        }
+        let mut payloads = vec![vec![]; peers.len()];
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let mut max_message_size = 0;
+
+        for round in 0..30 {
+            if round % 5 == 0 && round < 15 {
+                let vote = Observation::OpaquePayload(Transaction::new(format!("{}", round)));
+                for peer in &mut peers {
+                    unwrap!(peer.vote_for(vote.clone()));
+                }
+            }
+
+            for src in 0..peers.len() {
+                let dst = (src + rng.gen_range(1, peers.len())) % peers.len();
+                let src_id = peers[src].our_pub_id().clone();
+                let dst_id = peers[dst].our_pub_id().clone();
+
+                loop {
+                    let request = unwrap!(peers[src].create_gossip(&dst_id));
+                    let response = unwrap!(peers[dst].handle_request(&src_id, request.clone()));
+                    max_message_size = cmp::max(
+                        max_message_size,
+                        cmp::max(serialise(&request).len(), serialise(&response).len()),
+                    );
+                    unwrap!(peers[src].handle_response(&dst_id, response));
+
+                    if !peers[src].has_unfinished_gossip_with(&dst_id) {
+                        break;
+                    }
+                }
+            }
+
+            for (peer, peer_payloads) in peers.iter_mut().zip(&mut payloads) {
+                while let Some(block) = peer.poll() {
+                    peer_payloads.push(block.payload().clone());
+                }
+            }
+        }
+
+        (max_message_size, payloads)
+    }
+
+    let max_gossip_size = 1_000;
+    let (bounded_max_message_size, bounded_payloads) = run(Some(max_gossip_size));
+    let (max_message_size, payloads) = run(None);
+
+    // The limit was effective...
+    assert!(max_message_size > 2 * max_gossip_size);
+    assert!(bounded_max_message_size < max_gossip_size + 20);
+
+    // ...and made no difference to consensus.
+    assert!(payloads[0].len() > 1);
+    assert!(payloads
+        .iter()
+        .all(|peer_payloads| *peer_payloads == payloads[0]));
+    assert_eq!(bounded_payloads, payloads);
+}
+
+#[test]
+fn gossip_strategy() {
+    let ids = mock::create_ids(4);
+    let mut peers: Vec<TestPeer> = create_peers(ids.len());
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+
+    // By default, any of the other peers can be picked.
+    let recipient = unwrap!(peers[0].next_gossip_recipient(&mut rng));
+    assert!(ids[1..].contains(&recipient));
+
+    peers[0].set_gossip_strategy(GossipStrategy::RoundRobin);
+    let recipients: Vec<_> = (0..6)
+        .map(|_| unwrap!(peers[0].next_gossip_recipient(&mut rng)))
+        .collect();
+    let start = unwrap!(ids.iter().position(|id| *id == recipients[0]));
+    let expected: Vec<_> = ids[1..]
+        .iter()
+        .cycle()
+        .skip(start - 1)
+        .take(6)
+        .cloned()
+        .collect();
+    assert_eq!(recipients, expected);
+
+    // Bob and Carol gossiped to Alice, but Dave didn't yet.
+    exchange_gossip(&mut peers, 1, 0);
+    exchange_gossip(&mut peers, 2, 0);
+    peers[0].set_gossip_strategy(GossipStrategy::LeastRecentlySynced);
+    assert_eq!(
+        peers[0].next_gossip_recipient(&mut rng),
+        Some(ids[3].clone())
+    );
+    exchange_gossip(&mut peers, 3, 0);
+    assert_eq!(
+        peers[0].next_gossip_recipient(&mut rng),
+        Some(ids[1].clone())
+    );
+
+    // Now Alice knows everything Carol and Dave know, but Bob misses their events.
+    exchange_gossip(&mut peers, 0, 2);
+    exchange_gossip(&mut peers, 0, 3);
+    peers[0].set_gossip_strategy(GossipStrategy::MostMissingEvents);
+    assert_eq!(
+        peers[0].next_gossip_recipient(&mut rng),
+        Some(ids[1].clone())
+    );
+    exchange_gossip(&mut peers, 0, 1);
+    assert_ne!(
+        peers[0].next_gossip_recipient(&mut rng),
+        Some(ids[1].clone())
+    );
+}
+
+#[test]
+fn protocol_version() {
+    let ids = mock::create_ids(2);
+    let genesis_group: BTreeSet<_> = ids.iter().cloned().collect();
+    let mut alice =
+        TestParsec::from_genesis(ids[0].clone(), &genesis_group, ConsensusMode::Supermajority);
+    let mut bob =
+        TestParsec::from_genesis(ids[1].clone(), &genesis_group, ConsensusMode::Supermajority);
+
+    // Rewrites the version a serialised message is written in, as a newer peer would.
+    fn with_version(mut message: Vec<u8>, version: u32) -> Vec<u8> {
+        message[..4].copy_from_slice(&version.to_le_bytes());
+        message
+    }
+
+    let request = unwrap!(alice.create_gossip(bob.our_pub_id()));
+    assert_eq!(request.version(), PROTOCOL_VERSION);
+    let serialised = unwrap!(serialisation::serialise(&request));
+    let deserialised: Request<_, _> = unwrap!(serialisation::deserialise(&serialised));
+    assert_eq!(deserialised, request);
+
+    // A request in a version Bob doesn't support still deserialises, but can't be handled.
+    let newer: Request<Transaction, PeerId> = unwrap!(serialisation::deserialise(&with_version(
+        serialised.clone(),
+        PROTOCOL_VERSION + 1
+    )));
+    assert_eq!(
+        bob.handle_request(alice.our_pub_id(), newer),
+        Err(Error::IncompatibleVersion(PROTOCOL_VERSION + 1))
+    );
+
+    // The response is written in the version of the request.
+    let response = unwrap!(bob.handle_request(alice.our_pub_id(), request));
+    assert_eq!(response.version(), PROTOCOL_VERSION);
+    let serialised = unwrap!(serialisation::serialise(&response));
+    let newer: Response<Transaction, PeerId> = unwrap!(serialisation::deserialise(&with_version(
+        serialised.clone(),
+        PROTOCOL_VERSION + 1
+    )));
+    assert_eq!(
+        alice.handle_response(bob.our_pub_id(), newer),
+        Err(Error::IncompatibleVersion(PROTOCOL_VERSION + 1))
+    );
+    unwrap!(alice.handle_response(
+        bob.our_pub_id(),
+        unwrap!(serialisation::deserialise(&serialised))
+    ));
+}
+
+#[test]
+fn fetch_missing_events() {
+    let ids = mock::create_ids(3);
+    let mut peers: Vec<TestPeer> = create_peers(ids.len());
+
+    // Alice and Bob sync, so Alice has events of both of them to gossip to Carol.
+    exchange_gossip(&mut peers, 0, 1);
+    assert!(peers[2].create_fetch(&ids[0]).is_none());
+
+    // The first event of Alice's request to Carol gets lost, so Carol can't handle the rest.
+    let mut request = unwrap!(peers[0].create_gossip(&ids[2]));
+    let lost = request.packed_events.remove(0);
+    let error = unwrap_err!(peers[2].handle_request(&ids[0], request.clone()));
+    assert!(error == Error::UnknownSelfParent || error == Error::UnknownOtherParent);
+
+    // Carol fetches the missing event from Alice and can then handle the request.
+    let fetch = unwrap!(peers[2].create_fetch(&ids[0]));
+    assert_eq!(fetch.hashes(), &[lost.compute_hash()]);
+    assert_eq!(fetch.version(), PROTOCOL_VERSION);
+    let fetch: Fetch = unwrap!(serialisation::deserialise(&unwrap!(
+        serialisation::serialise(&fetch)
+    )));
+    let fetched = unwrap!(peers[0].handle_fetch(&ids[2], fetch));
+    assert!(fetched
+        .iter()
+        .any(|response| response.packed_events.contains(&lost)));
+    for response in fetched {
+        let response = unwrap!(serialisation::deserialise(&unwrap!(
+            serialisation::serialise(&response)
+        )));
+        unwrap!(peers[2].handle_fetched(&ids[0], response));
+    }
+    assert!(peers[2].create_fetch(&ids[0]).is_none());
+
+    let response = unwrap!(peers[2].handle_request(&ids[0], request));
+    unwrap!(peers[0].handle_response(&ids[2], response));
+
+    // A fetch can't ask for more events than a peer can be missing.
+    let fetch = Fetch::new(vec![lost.compute_hash(); MAX_FETCHED_HASHES + 1]);
+    assert_eq!(
+        peers[0].handle_fetch(&ids[2], fetch),
+        Err(Error::InvalidMessage)
+    );
+}
+
+#[test]
+fn out_of_order_events() {
+    let ids = mock::create_ids(4);
+    let mut peers: Vec<TestPeer> = create_peers(ids.len());
+
+    exchange_gossip(&mut peers, 0, 1);
+
+    // Carol receives Alice's events in reverse order within a single request.
+    let mut request = unwrap!(peers[0].create_gossip(&ids[2]));
+    let last = unwrap!(request.packed_events.pop());
+    request.packed_events.reverse();
+    request.packed_events.push(last);
+    let response = unwrap!(peers[2].handle_request(&ids[0], request.clone()));
+    unwrap!(peers[0].handle_response(&ids[2], response));
+    assert!(request
+        .packed_events
+        .iter()
+        .all(|packed_event| peers[2].graph().contains(&packed_event.compute_hash())));
+
+    // Dave receives the second half of Alice's events before the first one. They are kept until
+    // their parents arrive.
+    let mut request = unwrap!(peers[0].create_gossip(&ids[3]));
+    let half = request.packed_events.len() / 2;
+    let first_half: Vec<_> = request.packed_events.drain(..half).collect();
+    let error = unwrap_err!(peers[3].handle_request(&ids[0], request.clone()));
+    assert!(error == Error::UnknownSelfParent || error == Error::UnknownOtherParent);
+
+    // Dave fetches them from Alice, whose reply is split as it exceeds her gossip size limit.
+    let fetch = unwrap!(peers[3].create_fetch(&ids[0]));
+    peers[0].set_max_gossip_size(Some(1));
+    let fetched = unwrap!(peers[0].handle_fetch(&ids[3], fetch));
+    assert!(fetched.len() > 1);
+    assert!(fetched
+        .iter()
+        .all(|response| response.packed_events.len() == 1));
+    assert!(first_half.iter().all(|packed_event| fetched
+        .iter()
+        .any(|response| response.packed_events.contains(packed_event))));
+    for response in fetched {
+        unwrap!(peers[3].handle_fetched(&ids[0], response));
+    }
+    assert!(peers[3].create_fetch(&ids[0]).is_none());
+    assert!(request
+        .packed_events
+        .iter()
+        .all(|packed_event| peers[3].graph().contains(&packed_event.compute_hash())));
+}
+
+#[test]
+fn forged_orphan_event() {
+    let ids = mock::create_ids(4);
+    let mut peers: Vec<TestPeer> = create_peers(ids.len());
+
+    exchange_gossip(&mut peers, 0, 1);
+
+    // Dave buffers the second half of Alice's events until the first half arrives.
+    let mut request = unwrap!(peers[0].create_gossip(&ids[3]));
+    let half = request.packed_events.len() / 2;
+    let _ = request.packed_events.drain(..half);
+    let orphan = unwrap!(request.packed_events.last()).compute_hash();
+    let error = unwrap_err!(peers[3].handle_request(&ids[0], request));
+    assert!(error == Error::UnknownSelfParent || error == Error::UnknownOtherParent);
+
+    // An event following them but not signed by Alice is rejected rather than buffered too.
+    let impostor = unwrap!(
+        iter::repeat_with(|| PeerId::new_with_random_keypair(ids[0].id())).find(|id| *id != ids[0])
+    );
+    let forged = PackedEvent::new_request(impostor, orphan, orphan);
+    assert_eq!(
+        peers[3].handle_request(&ids[0], Request::new(vec![forged])),
+        Err(Error::SignatureFailure)
+    );
+}
+
+#[test]
+fn orphan_pool_per_peer_limit() {
+    let ids = mock::create_ids(4);
+    let mut peers: Vec<TestPeer> = create_peers(ids.len());
+
+    exchange_gossip(&mut peers, 0, 1);
+
+    // Dave buffers the second half of Alice's events until the first half arrives.
+    let mut request = unwrap!(peers[0].create_gossip(&ids[3]));
+    let half = request.packed_events.len() / 2;
+    let first_half: Vec<_> = request.packed_events.drain(..half).collect();
+    let error = unwrap_err!(peers[3].handle_request(&ids[0], request.clone()));
+    assert!(error == Error::UnknownSelfParent || error == Error::UnknownOtherParent);
+
+    // Events created under a throwaway id aren't buffered at all.
+    let orphan = unwrap!(request.packed_events.last()).compute_hash();
+    let stranger = PeerId::new("Zaida");
+    let stranger_event = PackedEvent::new_request(stranger.clone(), orphan, orphan);
+    assert_eq!(
+        peers[3].handle_request(&ids[1], Request::new(vec![stranger_event])),
+        Err(Error::UnknownPeer)
+    );
+
+    // Bob floods Dave with a chain of events whose first parent never arrives.
+    let mut parent = PackedEvent::new_initial(stranger).compute_hash();
+    for _ in 0..MAX_ORPHAN_EVENTS {
+        let orphan = PackedEvent::new_request(ids[1].clone(), parent, parent);
+        parent = orphan.compute_hash();
+        let _ = peers[3].handle_request(&ids[1], Request::new(vec![orphan]));
+    }
+
+    // Bob's events only pushed out his own, so Alice's are still inserted once the first half
+    // arrives.
+    unwrap!(peers[3].handle_fetched(&ids[0], FetchResponse::new(first_half)));
+    assert!(request
+        .packed_events
+        .iter()
+        .all(|packed_event| peers[3].graph().contains(&packed_event.compute_hash())));
+}
+
+#[test]
+fn consensus_listener() {
+    let mut peers: Vec<TestPeer> = create_peers(4);
+    let (sender, receiver) = mpsc::channel();
+    let mut groups = vec![vec![]; peers.len()];
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+
+    for round in 0..40 {
+        if round % 5 == 0 && round < 20 {
+            let vote = Observation::OpaquePayload(Transaction::new(format!("{}", round)));
+            for peer in &mut peers {
+                unwrap!(peer.vote_for(vote.clone()));
+            }
+        }
+
+        // Register the listener once there are already some blocks waiting to be polled.
+        if round == 10 {
+            assert!(peers[0].consensused_blocks().next().is_some());
+            peers[0].set_consensus_listener(sender.clone());
+        }
+
+        gossip_round(&mut peers, &mut rng);
+
+        if round >= 10 {
+            assert!(peers[0].poll().is_none());
+        }
+        for (peer, peer_groups) in peers.iter_mut().zip(&mut groups).skip(1) {
+            while let Some(group) = peer.poll_group() {
+                peer_groups.push(group);
+            }
+        }
+    }
+
+    // The listener got the same groups as the other peers polled, decided in the same elections.
+    groups[0] = receiver.try_iter().collect();
+    assert!(groups[0].len() > 2);
+    for peer_groups in &groups {
+        assert_eq!(peer_groups.len(), groups[0].len());
+        for ((blocks, election), (expected_blocks, expected_election)) in
+            peer_groups.iter().zip(&groups[0])
+        {
+            assert_eq!(blocks, expected_blocks);
+            assert_eq!(
+                election.sequence_number(),
+                expected_election.sequence_number()
+            );
+        }
+    }
+
+    assert!(groups[0]
+        .windows(2)
+        .all(|pair| pair[0].1.sequence_number() < pair[1].1.sequence_number()));
+    assert!(groups[0]
+        .iter()
+        .all(|(_, election)| peers[0].graph().contains(election.deciding_event())));
+}
+
+#[test]
+fn stats() {
+    let ids = mock::create_ids(4);
+    let mut peers: Vec<TestPeer> = create_peers(ids.len());
+
+    let stats = peers[0].stats();
+    assert_eq!(stats.graph_size, peers[0].graph().len());
+    assert_eq!(stats.gossip_bytes_sent, 0);
+    assert_eq!(stats.gossip_bytes_received, 0);
+
+    // The genesis observation isn't consensused yet either.
+    assert_eq!(stats.unconsensused_observations, 1);
+
+    let vote = Observation::OpaquePayload(Transaction::new("vote"));
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(vote.clone()));
+    }
+    assert_eq!(peers[0].stats().unconsensused_observations, 2);
+
+    let mut rng = XorShiftRng::from_seed([5, 6, 7, 8]);
+    while peers
+        .iter()
+        .any(|peer| peer.has_unconsensused_observations())
+    {
+        gossip_round(&mut peers, &mut rng);
+    }
+
+    let stats: Vec<_> = peers.iter().map(|peer| peer.stats()).collect();
+    for (peer, stats) in peers.iter().zip(&stats) {
+        assert_eq!(stats.graph_size, peer.graph().len());
+        assert_eq!(stats.pending_events, 0);
+        assert_eq!(stats.unconsensused_observations, 0);
+        assert_eq!(stats.accusations_raised, 0);
+        assert!(stats.meta_votes.keys().all(|id| ids.contains(id)));
+    }
+
+    // Every message sent has been received by exactly one other peer.
+    let bytes_sent: u64 = stats.iter().map(|stats| stats.gossip_bytes_sent).sum();
+    let bytes_received: u64 = stats.iter().map(|stats| stats.gossip_bytes_received).sum();
+    assert!(bytes_sent > 0);
+    assert_eq!(bytes_sent, bytes_received);
+}
+
+// Mock of a threshold signature scheme with a threshold of two shares. A share is valid if it
+// contains the message and the ID of its signer, and the signature of a message is the message.
+struct MockThresholdSigner(PeerId);
+
+impl ThresholdSigner<PeerId> for MockThresholdSigner {
+    fn sign_share(&self, message: &[u8]) -> Vec<u8> {
+        serialise(&(&self.0, message))
+    }
+
+    fn verify_share(&self, peer_id: &PeerId, message: &[u8], share: &[u8]) -> bool {
+        serialise(&(peer_id, message)) == share
+    }
+
+    fn combine_shares(
+        &self,
+        message: &[u8],
+        shares: &BTreeMap<PeerId, Vec<u8>>,
+    ) -> Option<Vec<u8>> {
+        if shares.len() >= 2 {
+            Some(message.to_vec())
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn threshold_coin() {
+    let mut peers: Vec<TestPeer> = create_peers(4);
+    for peer in &mut peers {
+        let signer = MockThresholdSigner(peer.our_pub_id().clone());
+        peer.set_common_coin(ThresholdCoin::new(signer));
+    }
+
+    let mut rng = XorShiftRng::from_seed([3, 3, 5, 7]);
+    let mut round = 0;
+    while round < 20
+        || peers
+            .iter()
+            .any(|peer| peer.has_unconsensused_observations())
+    {
+        // Votes cast while a meta-election is in progress make the meta-votes diverge, so that
+        // the coin has to be tossed.
+        if round % 3 == 0 && round < 20 {
+            let vote = Observation::OpaquePayload(Transaction::new(format!("{}", round)));
+            for peer in &mut peers {
+                unwrap!(peer.vote_for(vote.clone()));
+            }
+        }
+        round += 1;
+        assert!(round < 100, "Consensus not reached.");
+
+        gossip_round(&mut peers, &mut rng);
+    }
+
+    // The coin has been tossed using the shares published in the graph. Each toss needs two
+    // shares, however many times it was evaluated.
+    for peer in &peers {
+        let shares = peer
+            .graph()
+            .iter()
+            .filter(|event| event.inner().is_coin_share())
+            .count() as u64;
+        assert!(peer.stats().coin_tosses > 0);
+        assert!(2 * peer.stats().coin_tosses <= shares);
+    }
+
+    let mut blocks = vec![vec![]; peers.len()];
+    for (peer, peer_blocks) in peers.iter_mut().zip(&mut blocks) {
+        while let Some(block) = peer.poll() {
+            peer_blocks.push(block);
+        }
+    }
+    assert_eq!(blocks[0].len(), 8);
+    assert!(blocks.iter().all(|peer_blocks| *peer_blocks == blocks[0]));
+}
+
+#[test]
+fn validator_weights() {
+    let ids = mock::create_ids(5);
+    let (genesis_ids, eric_id) = (&ids[..4], ids[4].clone());
+    // Alice alone holds more than two thirds of the total weight.
+    let weights: BTreeMap<_, _> = genesis_ids
+        .iter()
+        .map(|id| (id.clone(), if *id == ids[0] { 10 } else { 1 }))
+        .collect();
+    let mut peers: Vec<TestPeer> = genesis_ids
+        .iter()
+        .map(|id| {
+            TestParsec::from_weighted_genesis(id.clone(), &weights, ConsensusMode::Supermajority)
+        })
+        .collect();
+
+    // Zero and excessive weights are rejected.
+    for weight in &[0, MAX_WEIGHT + 1, u64::MAX] {
+        let invalid = Observation::Add {
+            peer_id: eric_id.clone(),
+            related_info: vec![],
+            weight: *weight,
+        };
+        assert_eq!(peers[0].vote_for(invalid), Err(Error::InvalidWeight));
+    }
+
+    // Votes by Alice only are enough to reach consensus, including on changing Dave's weight and
+    // adding Eric.
+    let payload = Observation::OpaquePayload(Transaction::new("weighted"));
+    let reweight = Observation::Add {
+        peer_id: ids[3].clone(),
+        related_info: vec![],
+        weight: 5,
+    };
+    let add_eric = Observation::Add {
+        peer_id: eric_id.clone(),
+        related_info: vec![],
+        weight: 1,
+    };
+    unwrap!(peers[0].vote_for(payload.clone()));
+    unwrap!(peers[0].vote_for(reweight.clone()));
+    unwrap!(peers[0].vote_for(add_eric.clone()));
+
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    gossip_until_blocks(&mut peers, &mut rng, 4);
+
+    for peer in &mut peers {
+        let payloads: BTreeSet<_> = peer
+            .consensused_blocks()
+            .skip(1)
+            .map(|block| block.payload().clone())
+            .collect();
+        assert_eq!(
+            payloads,
+            vec![payload.clone(), reweight.clone(), add_eric.clone()]
+                .into_iter()
+                .collect()
+        );
+
+        let dave_index = unwrap!(peer.get_peer_index(&ids[3]));
+        assert_eq!(peer.peer_list().peer_weight(dave_index), 5);
+    }
+
+    // Eric learns the genesis weights from the genesis events gossiped to him.
+    let genesis_group = genesis_ids.iter().cloned().collect();
+    let mut eric = TestParsec::<Transaction, _>::from_existing(
+        eric_id.clone(),
+        &genesis_group,
+        &genesis_group,
+        ConsensusMode::Supermajority,
+    );
+    let request = unwrap!(peers[0].create_gossip(&eric_id));
+    let _ = unwrap!(eric.handle_request(&ids[0], request));
+
+    let alice_index = unwrap!(eric.get_peer_index(&ids[0]));
+    assert_eq!(eric.peer_list().peer_weight(alice_index), 10);
+    let payloads: Vec<_> = eric
+        .consensused_blocks()
+        .map(|block| block.payload().clone())
+        .collect();
+    let alice_payloads: Vec<_> = peers[0]
+        .consensused_blocks()
+        .map(|block| block.payload().clone())
+        .collect();
+    assert_eq!(payloads, alice_payloads);
+}
+
+#[test]
+fn consensus_mode_threshold() {
+    let mut peers: Vec<TestPeer> = create_peers_with_mode(5, ConsensusMode::Threshold(50));
+
+    // Three votes out of five are a majority, but not a supermajority.
+    let vote = Observation::OpaquePayload(Transaction::new("majority"));
+    for peer in &mut peers[..3] {
+        unwrap!(peer.vote_for(vote.clone()));
+    }
+
+    // The highest valid threshold is reached once all the peers voted.
+    let unanimous = Observation::OpaquePayload(Transaction::new("unanimous"));
+    for peer in &mut peers {
+        unwrap!(peer.vote_for_with_mode(unanimous.clone(), ConsensusMode::Threshold(99)));
+    }
+
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    gossip_until_blocks(&mut peers, &mut rng, 3);
+
+    for peer in &mut peers {
+        // Skip the genesis block.
+        let _ = unwrap!(peer.poll());
+        let blocks = vec![unwrap!(peer.poll()), unwrap!(peer.poll())];
+        let block = unwrap!(blocks.iter().find(|block| *block.payload() == vote));
+        assert_eq!(block.proofs().len(), 3);
+        let block = unwrap!(blocks.iter().find(|block| *block.payload() == unanimous));
+        assert_eq!(block.proofs().len(), 5);
+    }
+}
+
+#[test]
+fn vote_for_with_mode() {
+    let mut peers: Vec<TestPeer> = create_peers(4);
+
+    // A single vote is enough for this payload, even though the section requires a supermajority.
+    let vote = Observation::OpaquePayload(Transaction::new("single"));
+    unwrap!(peers[0].vote_for_with_mode(vote.clone(), ConsensusMode::Single));
+    assert!(peers[0].have_voted_for(&vote));
+    assert_eq!(
+        peers[0].vote_for(vote.clone()).err(),
+        Some(Error::DuplicateVote)
+    );
+
+    // A threshold of 100% or more could never be reached.
+    let unreachable = Observation::OpaquePayload(Transaction::new("unreachable"));
+    for percent in &[100, 101] {
+        assert_eq!(
+            peers[0]
+                .vote_for_with_mode(unreachable.clone(), ConsensusMode::Threshold(*percent))
+                .err(),
+            Some(Error::InvalidConsensusMode)
+        );
+    }
+
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    gossip_until_blocks(&mut peers, &mut rng, 2);
+
+    let voter_id = peers[0].our_pub_id().clone();
+    for peer in &mut peers {
+        // Skip the genesis block.
+        let _ = unwrap!(peer.poll());
+        let block = unwrap!(peer.poll());
+        assert_eq!(*block.payload(), vote);
+        assert_eq!(block.consensus_mode(), ConsensusMode::Single);
+        assert_eq!(
+            block.proofs().iter().map(Proof::public_id).only(),
+            &voter_id
+        );
+    }
+}
+
+#[test]
+fn vote_for_with_ttl() {
+    let mut peers: Vec<TestPeer> = create_peers(4);
+
+    // Nobody else votes for this payload, so it can never be consensused. Its vote expires once
+    // the genesis block and the next one are consensused.
+    let lonely = Observation::OpaquePayload(Transaction::new("lonely"));
+    assert_eq!(
+        peers[0].vote_for_with_ttl(lonely.clone(), 0),
+        Err(Error::InvalidTtl)
+    );
+    assert!(!peers[0].have_voted_for(&lonely));
+    unwrap!(peers[0].vote_for_with_ttl(lonely.clone(), 2));
+    assert!(peers[0].have_voted_for(&lonely));
+
+    let common = Observation::OpaquePayload(Transaction::new("common"));
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(common.clone()));
+    }
+
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let mut round = 0;
+    while peers
+        .iter()
+        .any(|peer| peer.consensused_blocks().count() < 2 || peer.has_unconsensused_observations())
+    {
+        round += 1;
+        assert!(round < 100, "Consensus not reached.");
+
+        gossip_round(&mut peers, &mut rng);
+    }
+
+    for peer in &mut peers {
+        let payloads: Vec<_> = peer
+            .consensused_blocks()
+            .map(|block| block.payload().clone())
+            .collect();
+        assert!(payloads.contains(&common));
+        assert!(!payloads.contains(&lonely));
+    }
+
+    // The expired payload can be voted for again.
+    assert!(!peers[0].have_voted_for(&lonely));
+    unwrap!(peers[0].vote_for(lonely));
+    assert!(peers[0].has_unconsensused_observations());
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+struct PrioritisedTransaction(i32, String);
+
+impl NetworkEvent for PrioritisedTransaction {
+    fn priority(&self) -> i32 {
+        self.0
+    }
+}
+
+#[test]
+fn payload_priority() {
+    let mut peers: Vec<TestParsec<PrioritisedTransaction, _>> = create_peers(4);
+
+    let mut votes: Vec<_> = (0..6)
+        .map(|index| {
+            let priority = if index % 3 == 0 { i32::MAX } else { -1 };
+            Observation::OpaquePayload(PrioritisedTransaction(priority, format!("{}", index)))
+        })
+        .collect();
+    votes.push(Observation::Add {
+        peer_id: PeerId::new("Eric"),
+        related_info: vec![],
+        weight: 1,
+    });
+    for (position, peer) in peers.iter_mut().enumerate() {
+        for vote in votes.iter().cycle().skip(position).take(votes.len()) {
+            unwrap!(peer.vote_for(vote.clone()));
+        }
+    }
+
+    let mut groups = vec![vec![]; peers.len()];
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let mut round = 0;
+    while groups
+        .iter()
+        .any(|peer_groups| peer_groups.iter().map(Vec::len).sum::<usize>() < 8)
+    {
+        round += 1;
+        assert!(round < 100, "Consensus not reached.");
+
+        gossip_round(&mut peers, &mut rng);
+
+        for (peer, peer_groups) in peers.iter_mut().zip(&mut groups) {
+            while let Some((blocks, _)) = peer.poll_group() {
+                peer_groups.push(
+                    blocks
+                        .into_iter()
+                        .map(|block| block.payload().clone())
+                        .collect::<Vec<_>>(),
+                );
+            }
+        }
+    }
+
+    // Within every group, the high-priority payloads come before the others, and the membership
+    // changes before all of them.
+    assert!(groups[0].iter().any(|payloads| payloads.len() > 1));
+    for peer_groups in &groups {
+        assert_eq!(*peer_groups, groups[0]);
+        for payloads in peer_groups {
+            let priorities: Vec<_> = payloads
+                .iter()
+                .map(|payload| match payload {
+                    Observation::OpaquePayload(transaction) => Some(transaction.priority()),
+                    _ => None,
+                })
+                .collect();
+            assert!(priorities.windows(2).all(|pair| match (pair[0], pair[1]) {
+                (Some(lhs), Some(rhs)) => lhs >= rhs,
+                (Some(_), None) => false,
+                (None, _) => true,
+            }));
+        }
+    }
+}
+
+#[test]
+fn rekey() {
+    let ids = mock::create_ids(4);
+    let mut peers: Vec<TestPeer> = create_peers(ids.len());
+
+    let mut rng = XorShiftRng::from_seed([5, 6, 7, 8]);
+
+    // Only Bob's old id can prove the rotation of Bob's key.
+    let new_bob = PeerId::new_with_random_keypair("Bob");
+    let forged_rekey = match Observation::<Transaction, _>::new_rekey(&ids[2], new_bob.clone()) {
+        Observation::Rekey { new, proof, .. } => Observation::Rekey {
+            old: ids[1].clone(),
+            new,
+            proof,
+        },
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        peers[0].vote_for(forged_rekey).err(),
+        Some(Error::SignatureFailure)
+    );
+
+    let rekey = Observation::new_rekey(&ids[1], new_bob.clone());
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(rekey.clone()));
+    }
+    assert_eq!(
+        peers[1].switch_to_rekeyed_id(new_bob.clone()).err(),
+        Some(Error::UnknownPeer)
+    );
+    gossip_until_blocks(&mut peers, &mut rng, 2);
+
+    // Bob keeps his place in the section and signs his next vote with the new key.
+    unwrap!(peers[1].switch_to_rekeyed_id(new_bob.clone()));
+    let payload = Observation::OpaquePayload(Transaction::new("after rekey"));
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(payload.clone()));
+    }
+    gossip_until_blocks(&mut peers, &mut rng, 3);
+
+    for peer in &mut peers {
+        assert_eq!(peer.get_peer_index(&new_bob), peer.get_peer_index(&ids[1]));
+
+        let blocks: Vec<_> = peer.consensused_blocks().skip(1).collect();
+        assert_eq!(*blocks[0].payload(), rekey);
+        assert!(blocks[0].is_signed_by(&ids[1]));
+        assert_eq!(*blocks[1].payload(), payload);
+        assert!(blocks[1].is_signed_by(&new_bob));
+        assert!(!blocks[1].is_signed_by(&ids[1]));
+    }
+
+    // Bob's old key is revoked: an event he creates after the rotation and signs with it is
+    // rejected.
+    let mut stale_bob = unwrap!(TestParsec::restore(ids[1].clone(), &peers[1].snapshot()));
+    unwrap!(stale_bob.vote_for(Observation::OpaquePayload(Transaction::new("stale"))));
+    let request = unwrap!(stale_bob.create_gossip(&ids[0]));
+    assert_eq!(
+        peers[0].handle_request(&ids[1], request).err(),
+        Some(Error::SignatureFailure)
+    );
+}
+
+#[test]
+fn joint_consensus_needs_previous_voters() {
+    let ids = mock::create_ids(5);
+    let genesis_group: BTreeSet<_> = ids[..4].iter().cloned().collect();
+    let mut peers: Vec<TestPeer> = ids[..4]
+        .iter()
+        .map(|id| {
+            TestParsec::from_genesis(id.clone(), &genesis_group, ConsensusMode::Supermajority)
+        })
+        .collect();
+    for peer in &mut peers {
+        peer.set_joint_consensus(true);
+    }
+
+    let mut rng = XorShiftRng::from_seed([9, 10, 11, 12]);
+
+    // Eric joins with more than two thirds of the total weight of the new voters.
+    let add_eric = Observation::Add {
+        peer_id: ids[4].clone(),
+        related_info: vec![],
+        weight: 20,
+    };
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(add_eric.clone()));
+    }
+    gossip_until_blocks(&mut peers, &mut rng, 2);
+
+    let mut eric = TestParsec::from_existing(
+        ids[4].clone(),
+        &genesis_group,
+        &genesis_group,
+        ConsensusMode::Supermajority,
+    );
+    eric.set_joint_consensus(true);
+    peers.insert(2, eric);
+    let mut round = 0;
+    while !peers[2]
+        .consensused_blocks()
+        .any(|block| *block.payload() == add_eric)
+    {
+        round += 1;
+        assert!(round < 100, "Eric didn't catch up.");
+
+        gossip_round(&mut peers[..3], &mut rng);
+    }
+
+    // Alice, Bob and Eric are a supermajority of the new voters but not of the previous ones, so
+    // they can't reach consensus until Carol, who voted too, gossips again.
+    let payload = Observation::OpaquePayload(Transaction::new("joint"));
+    for peer in &mut peers[..4] {
+        unwrap!(peer.vote_for(payload.clone()));
+    }
+    for _ in 0..50 {
+        gossip_round(&mut peers[..3], &mut rng);
+    }
+    assert!(peers[..2]
+        .iter()
+        .all(|peer| peer.consensused_blocks().count() == 2));
+
+    gossip_until_blocks(&mut peers[..4], &mut rng, 3);
+    for peer in &peers[..2] {
+        assert_eq!(
+            *unwrap!(peer.consensused_blocks().nth(2)).payload(),
+            payload
+        );
+    }
+}
+
+#[test]
+fn joint_consensus_counts_previous_weights() {
+    let ids = mock::create_ids(4);
+    let genesis_group: BTreeSet<_> = ids.iter().cloned().collect();
+    let mut peers: Vec<TestPeer> = ids
+        .iter()
+        .map(|id| {
+            TestParsec::from_genesis(id.clone(), &genesis_group, ConsensusMode::Supermajority)
+        })
+        .collect();
+    for peer in &mut peers {
+        peer.set_joint_consensus(true);
+    }
+
+    let mut rng = XorShiftRng::from_seed([9, 10, 11, 12]);
+
+    // Alice gets more than two thirds of the total weight.
+    let reweight_alice = Observation::Add {
+        peer_id: ids[0].clone(),
+        related_info: vec![],
+        weight: 20,
+    };
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(reweight_alice.clone()));
+    }
+    gossip_until_blocks(&mut peers, &mut rng, 2);
+
+    // Alice alone is a supermajority of the new weights, but she only held a quarter of the
+    // previous ones, so her vote isn't enough during the transition.
+    let payload = Observation::OpaquePayload(Transaction::new("heavy"));
+    unwrap!(peers[0].vote_for(payload.clone()));
+    for _ in 0..50 {
+        gossip_round(&mut peers, &mut rng);
+    }
+    assert!(peers
+        .iter()
+        .all(|peer| peer.consensused_blocks().count() == 2));
+
+    for peer in &mut peers[1..3] {
+        unwrap!(peer.vote_for(payload.clone()));
+    }
+    gossip_until_blocks(&mut peers, &mut rng, 3);
+    for peer in &peers {
+        assert_eq!(
+            *unwrap!(peer.consensused_blocks().nth(2)).payload(),
+            payload
+        );
+    }
+}
+
+#[test]
+fn reconfigure() {
+    let ids = mock::create_ids(5);
+    let genesis_group: BTreeSet<_> = ids[..4].iter().cloned().collect();
+    // Dave is not needed for the supermajority and takes no part.
+    let mut peers: Vec<_> = ids[..3]
+        .iter()
+        .map(|id| {
+            TestParsec::from_genesis(id.clone(), &genesis_group, ConsensusMode::Supermajority)
+        })
+        .collect();
+
+    // Replace Dave with Eric in a single block.
+    let reconfigure = Observation::Reconfigure {
+        add: iter::once((ids[4].clone(), 2)).collect(),
+        remove: iter::once(ids[3].clone()).collect(),
+        related_info: vec![],
+    };
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(reconfigure.clone()));
+    }
+
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    gossip_until_blocks(&mut peers, &mut rng, 2);
+
+    let expected_voters: BTreeSet<_> = vec![&ids[0], &ids[1], &ids[2], &ids[4]]
+        .into_iter()
+        .collect();
+    for peer in &mut peers {
+        let blocks: Vec<_> = peer.consensused_blocks().collect();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(*blocks[1].payload(), reconfigure);
+
+        let voters: BTreeSet<_> = peer
+            .peer_list()
+            .voters()
+            .map(|(_, peer)| peer.id())
+            .collect();
+        assert_eq!(voters, expected_voters);
+
+        let eric_index = unwrap!(peer.get_peer_index(&ids[4]));
+        assert_eq!(peer.peer_list().peer_weight(eric_index), 2);
+        let dave_index = unwrap!(peer.get_peer_index(&ids[3]));
+        assert_eq!(
+            peer.peer_list().peer_state(dave_index),
+            PeerState::inactive()
+        );
+    }
+}
+
+#[test]
+fn from_checkpoint() {
+    let ids = mock::create_ids(5);
+    let mut peers: Vec<TestPeer> = create_peers(4);
+    let mut payloads = vec![vec![]; peers.len()];
+
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let mut gossip_until_payloads =
+        |peers: &mut [TestPeer], payloads: &mut [Vec<_>], count: usize| {
+            let mut round = 0;
+            while payloads
+                .iter()
+                .any(|peer_payloads| peer_payloads.len() < count)
+            {
+                round += 1;
+                assert!(round < 100, "Consensus not reached.");
+
+                for src in 0..peers.len() {
+                    let dst = (src + rng.gen_range(1, peers.len())) % peers.len();
+                    let src_id = peers[src].our_pub_id().clone();
+                    let dst_id = peers[dst].our_pub_id().clone();
+
+                    // Until they exchanged gossip, Eric and the others can only receive from
+                    // each other.
+                    let request = match peers[src].create_gossip(&dst_id) {
+                        Err(Error::InvalidPeerState { .. }) => continue,
+                        request => unwrap!(request),
+                    };
+                    let response = unwrap!(peers[dst].handle_request(&src_id, request));
+                    unwrap!(peers[src].handle_response(&dst_id, response));
+                }
+
+                for (peer, peer_payloads) in peers.iter_mut().zip(payloads.iter_mut()) {
+                    while let Some(block) = peer.poll() {
+                        peer_payloads.push(block.payload().clone());
+                    }
+                    let _ = peer.prune();
+                }
+            }
+        };
+
+    let add_eric = Observation::Add {
+        peer_id: ids[4].clone(),
+        weight: 1,
+        related_info: vec![],
+    };
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(add_eric.clone()));
+    }
+    gossip_until_payloads(&mut peers, &mut payloads, 2);
+
+    // Every voter signs the same checkpoint.
+    let mut checkpoint = unwrap!(peers[0].create_checkpoint());
+    assert!(!checkpoint.is_signed_by_supermajority());
+    assert_eq!(
+        TestParsec::<Transaction, _>::from_checkpoint(
+            ids[4].clone(),
+            &checkpoint,
+            ConsensusMode::Supermajority
+        )
+        .err(),
+        Some(Error::InvalidCheckpoint)
+    );
+    for peer in &mut peers[1..] {
+        unwrap!(checkpoint.merge(unwrap!(peer.create_checkpoint())));
+    }
+    assert!(checkpoint.is_signed_by_supermajority());
+    assert_eq!(checkpoint.block_count(), 2);
+    assert!(checkpoint.voters().contains(&ids[4]));
+
+    // Eric joins from the checkpoint without receiving the events preceding it, such as the votes
+    // for adding Eric, and reaches the same consensus as everyone else.
+    peers.push(unwrap!(TestParsec::from_checkpoint(
+        ids[4].clone(),
+        &checkpoint,
+        ConsensusMode::Supermajority
+    )));
+    let mut payloads: Vec<_> = payloads.into_iter().chain(iter::once(vec![])).collect();
+    let vote = Observation::OpaquePayload(Transaction::new("after checkpoint"));
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(vote.clone()));
+    }
+    gossip_until_payloads(&mut peers, &mut payloads, 1);
+    gossip_until_payloads(&mut peers[..4], &mut payloads[..4], 3);
+
+    assert!(peers[4]
+        .graph()
+        .iter()
+        .all(|event| peers[4].event_payload(&event) != Some(&add_eric)));
+    assert_eq!(payloads[4], vec![vote]);
+    assert_eq!(peers[4].last_block_hash(), peers[0].last_block_hash());
+    assert!(payloads[..4]
+        .iter()
+        .all(|peer_payloads| *peer_payloads == payloads[0] && peer_payloads[2] == payloads[4][0]));
+}
+
+#[test]
+fn chained_blocks() {
+    let ids = mock::create_ids(4);
+    let mut peers: Vec<TestPeer> = create_peers(ids.len());
+    let mut blocks = vec![vec![]; peers.len()];
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+
+    for round in 0..40 {
+        if round % 5 == 0 && round < 20 {
+            let vote = Observation::OpaquePayload(Transaction::new(format!("{}", round)));
+            for peer in &mut peers {
+                unwrap!(peer.vote_for(vote.clone()));
+            }
+        }
+
+        gossip_round(&mut peers, &mut rng);
+
+        for (peer, peer_blocks) in peers.iter_mut().zip(&mut blocks) {
+            while let Some(block) = peer.poll() {
+                peer_blocks.push(block);
+            }
+        }
+    }
+
+    // Every block follows the previous one, and all the peers have the same chain even though
+    // their blocks might carry different proofs.
+    let hashes: Vec<_> = blocks[0].iter().map(Block::hash).collect();
+    assert!(hashes.len() > 2);
+    assert_eq!(blocks[0][0].previous_hash(), None);
+    for (peer, peer_blocks) in peers.iter().zip(&blocks) {
+        for (sequence_number, block) in peer_blocks.iter().enumerate() {
+            assert_eq!(block.sequence_number(), sequence_number);
+            if sequence_number > 0 {
+                assert_eq!(block.previous_hash(), Some(&hashes[sequence_number - 1]));
+                assert!(block.follows(&peer_blocks[sequence_number - 1]));
+                assert!(!peer_blocks[sequence_number - 1].follows(block));
+            }
+        }
+        assert_eq!(
+            peer_blocks.iter().map(Block::hash).collect::<Vec<_>>(),
+            hashes
+        );
+        assert_eq!(peer.last_block_hash(), hashes.last());
+    }
+}
+
+#[test]
+fn block_certificate() {
+    let ids = mock::create_ids(5);
+    let mut peers: Vec<TestPeer> = create_peers(4);
+
+    let payload = Observation::OpaquePayload(Transaction::new("certified"));
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(payload.clone()));
+    }
+
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    gossip_until_blocks(&mut peers, &mut rng, 2);
+
+    // A consensused block can be verified by anyone knowing its voters.
+    let voters: BTreeMap<_, _> = ids[..4].iter().map(|id| (id.clone(), 1)).collect();
+    let block = unwrap!(peers[0].consensused_blocks().nth(1)).clone();
+    assert_eq!(*block.payload(), payload);
+    let certificate = BlockCertificate::new(block, &voters);
+    unwrap!(verify_block(&certificate, &voters));
+
+    // But not against different voters...
+    let mut other_voters = voters.clone();
+    let _ = other_voters.insert(ids[4].clone(), 5);
+    assert_eq!(
+        verify_block(&certificate, &other_voters),
+        Err(Error::MismatchedVoters)
+    );
+    let certificate = BlockCertificate::new(certificate.into_block(), &other_voters);
+    assert_eq!(
+        verify_block(&certificate, &other_voters),
+        Err(Error::InsufficientProofs)
+    );
+
+    // ...nor if signed by half of the voters only.
+    let votes: BTreeMap<_, _> = ids[..2]
+        .iter()
+        .map(|id| (id.clone(), Vote::new(id, payload.clone())))
+        .collect();
+    let mut block = unwrap!(Block::new(&votes));
+    let certificate = BlockCertificate::new(block.clone(), &voters);
+    assert_eq!(
+        verify_block(&certificate, &voters),
+        Err(Error::InsufficientProofs)
+    );
+
+    // Nor by counting a voter twice, here through a second proof by a key with the same name.
+    // Other tests seeding the key generation concurrently might make it produce the same key.
+    let duplicate_id = unwrap!(
+        iter::repeat_with(|| PeerId::new_with_random_keypair(ids[0].id())).find(|id| *id != ids[0])
+    );
+    let duplicate_vote = Vote::new(&duplicate_id, payload.clone());
+    assert!(unwrap!(block.add_vote(&duplicate_id, &duplicate_vote)));
+    assert_eq!(block.proofs().len(), 3);
+    let certificate = BlockCertificate::new(block, &voters);
+    assert_eq!(
+        verify_block(&certificate, &voters),
+        Err(Error::InsufficientProofs)
+    );
+
+    // The voters sign the consensus mode of their votes, so a block can't be passed off as
+    // consensused with a different mode than they requested...
+    let votes: BTreeMap<_, _> = ids[..4]
+        .iter()
+        .map(|id| {
+            let vote = Vote::new_with_mode(id, payload.clone(), ConsensusMode::Single);
+            (id.clone(), vote)
+        })
+        .collect();
+    let block = unwrap!(Block::new(&votes));
+    let certificate = BlockCertificate::new(block.clone(), &voters);
+    assert_eq!(
+        verify_block(&certificate, &voters),
+        Err(Error::InsufficientProofs)
+    );
+    let certificate =
+        BlockCertificate::new(block.with_consensus_mode(ConsensusMode::Single), &voters);
+    unwrap!(verify_block(&certificate, &voters));
+
+    // ...nor can a vote be given another consensus mode or expiry than the voter signed.
+    let vote = Vote::new_with_terms(&ids[0], payload.clone(), None, Some(10));
+    assert!(vote.is_valid(&ids[0]));
+    let forged_votes = vec![
+        Vote::new_with_terms(&ids[0], payload.clone(), None, None),
+        Vote::new_with_terms(&ids[0], payload.clone(), None, Some(11)),
+        Vote::new_with_terms(
+            &ids[0],
+            payload,
+            Some(ConsensusMode::Supermajority),
+            Some(10),
+        ),
+    ];
+    for forged_vote in forged_votes {
+        assert!(!ids[0].verify_signature(vote.signature(), &forged_vote.signed_data()));
+    }
+}
+
 #[cfg(feature = "malice-detection")]
 mod handle_malice {
     use super::*;
@@ -514,7 +2019,7 @@ mod handle_malice {
         dev_utils::{parse_test_dot_file, ParsedContents},
         gossip::{Event, EventHash},
         id::SecretId,
-        mock::{self, Transaction},
+        mock::Transaction,
         network_event::NetworkEvent,
         observation::Malice,
         peer_list::{PeerIndex, PeerList, PeerState},
@@ -588,9 +2093,13 @@ mod handle_malice {
         ));
         let d_1_index = dave_contents.add_event(d_1);
 
-        let d_2 = unwrap!(
-            dave_contents.new_event_from_observation(d_1_index, Observation::Genesis(genesis),)
-        );
+        let d_2 = unwrap!(dave_contents.new_event_from_observation(
+            d_1_index,
+            Observation::Genesis {
+                group: genesis,
+                weights: BTreeMap::new(),
+            },
+        ));
         let d_2_hash = *d_2.hash();
         let _ = dave_contents.add_event(d_2);
 
@@ -643,9 +2152,13 @@ mod handle_malice {
         let e_0 = Event::new_initial(eric_contents.event_context());
         let e_0_index = eric_contents.add_event(e_0);
 
-        let e_1 = unwrap!(
-            eric_contents.new_event_from_observation(e_0_index, Observation::Genesis(genesis),)
-        );
+        let e_1 = unwrap!(eric_contents.new_event_from_observation(
+            e_0_index,
+            Observation::Genesis {
+                group: genesis,
+                weights: BTreeMap::new(),
+            },
+        ));
         let e_1_hash = *e_1.hash();
         let _ = eric_contents.add_event(e_1);
 
@@ -671,13 +2184,7 @@ mod handle_malice {
     }
 
     fn initialise_genesis_parsecs(count: usize) -> Vec<TestPeer> {
-        let genesis_ids = mock::create_ids(count).into_iter().collect::<BTreeSet<_>>();
-        genesis_ids
-            .iter()
-            .map(|id| {
-                TestParsec::from_genesis(id.clone(), &genesis_ids, ConsensusMode::Supermajority)
-            })
-            .collect()
+        create_peers(count)
     }
 
     // Asserts that these and only these accusations have been made by `peer`.
@@ -704,7 +2211,7 @@ mod handle_malice {
         // Pop Alice's last event, which is her genesis vote.
         let (_, genesis_event) = unwrap!(alice.remove_last_event());
         match unwrap!(alice.event_payload(&genesis_event)) {
-            Observation::Genesis(_) => (),
+            Observation::Genesis { .. } => (),
             _ => panic!("This should be Alice's genesis vote."),
         }
 
@@ -736,7 +2243,10 @@ mod handle_malice {
             bob.our_pub_id().clone(),
             PeerId::new("Derp")
         ];
-        unwrap!(alice.vote_for(Observation::Genesis(invalid_genesis)));
+        unwrap!(alice.vote_for(Observation::Genesis {
+            group: invalid_genesis,
+            weights: BTreeMap::new(),
+        }));
 
         // Create request from Alice to Carol.
         let request = unwrap!(alice.create_gossip(carol.our_pub_id()));
@@ -826,7 +2336,7 @@ mod handle_malice {
         let invalid_req_hash = invalid_req.compute_hash();
         let mut packed_events = take_packed_events(&bob, bob.graph().len());
         packed_events.push(invalid_req.clone());
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
 
         let expected_malice = Malice::InvalidRequest(Box::new(invalid_req));
 
@@ -874,7 +2384,7 @@ mod handle_malice {
 
         let mut packed_events = take_packed_events(&bob, 2);
         packed_events.push(invalid_req.clone());
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
 
         assert_handling_invalid_response(
             &mut bob,
@@ -887,7 +2397,7 @@ mod handle_malice {
         packed_events = take_packed_events(&bob, 5);
         packed_events.push(invalid_req);
         // Knowledge of Alice and Bob, and the invalid_req.
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
 
         assert_handling_invalid_response(
             &mut bob,
@@ -926,7 +2436,7 @@ mod handle_malice {
 
         let mut packed_events = take_packed_events(&bob, bob.graph().len());
         packed_events.push(invalid_req);
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
         assert_handling_invalid_response(
             &mut bob,
             &mut carol,
@@ -956,7 +2466,7 @@ mod handle_malice {
         // If the response be sent to Carol, a response event shall not be created.
         let packed_events = take_packed_events(&bob, bob.graph().len());
         assert_eq!(
-            carol.handle_response(bob.our_pub_id(), Response { packed_events }),
+            carol.handle_response(bob.our_pub_id(), Response::new(packed_events)),
             Err(Error::InvalidMessage)
         );
         assert!(!carol.graph().iter().any(|event| event.is_response()));
@@ -974,7 +2484,7 @@ mod handle_malice {
         let mut packed_events = take_packed_events(&carol, 2);
         packed_events.push(bob_request);
         packed_events.push(invalid_resp.clone());
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
         assert_handling_invalid_response(
             &mut carol,
             &mut bob,
@@ -986,7 +2496,7 @@ mod handle_malice {
         packed_events = take_packed_events(&bob, 8);
         packed_events.push(invalid_resp);
         // Knowledge of Alice, Bob and Carol, and the invalid_resp.
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
         assert_handling_invalid_response(
             &mut carol,
             &mut dave,
@@ -1018,9 +2528,7 @@ mod handle_malice {
         let invalid_resp_hash = invalid_resp.compute_hash();
         let expected_malice = Malice::InvalidResponse(Box::new(invalid_resp.clone()));
 
-        let invalid_response_msg = Response {
-            packed_events: vec![invalid_resp.clone()],
-        };
+        let invalid_response_msg = Response::new(vec![invalid_resp.clone()]);
         assert_handling_invalid_response(
             &mut alice,
             &mut bob,
@@ -1032,7 +2540,7 @@ mod handle_malice {
         let mut packed_events = take_packed_events(&bob, 6);
         packed_events.push(invalid_resp);
         // Knowledge of Alice and Bob, and the invalid_resp.
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
         assert_handling_invalid_response(
             &mut alice,
             &mut carol,
@@ -1065,9 +2573,7 @@ mod handle_malice {
         let invalid_resp_hash = invalid_resp.compute_hash();
         let expected_malice = Malice::InvalidResponse(Box::new(invalid_resp.clone()));
 
-        let invalid_response_msg = Response {
-            packed_events: vec![valid_resp, invalid_resp.clone()],
-        };
+        let invalid_response_msg = Response::new(vec![valid_resp, invalid_resp.clone()]);
         assert_handling_invalid_response(
             &mut alice,
             &mut bob,
@@ -1078,7 +2584,7 @@ mod handle_malice {
 
         let mut packed_events = take_packed_events(&alice, alice.graph().len());
         packed_events.push(invalid_resp);
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
         assert_handling_invalid_response(
             &mut alice,
             &mut carol,
@@ -1337,9 +2843,7 @@ mod handle_malice {
         let a_1 = unwrap!(nth_event(alice.graph(), 1).pack(alice.event_context()));
         let a_2_0 =
             PackedEvent::new_requesting(alice_id.clone(), bob_id.clone(), a_1.compute_hash());
-        let mut request = Request {
-            packed_events: vec![a_0.clone(), a_1.clone(), a_2_0.clone()],
-        };
+        let mut request = Request::new(vec![a_0.clone(), a_1.clone(), a_2_0.clone()]);
         unwrap!(bob.handle_request(&alice_id, request.clone()));
 
         // [A_0, A_1, A_2,1, A_3,0] will be sent to Carol.
@@ -1434,9 +2938,8 @@ mod handle_malice {
             PackedEvent::new_requesting(alice_id.clone(), bob_id.clone(), a_2_0.compute_hash());
         let a_3_1 =
             PackedEvent::new_requesting(alice_id.clone(), bob_id.clone(), a_2_1.compute_hash());
-        let mut request = Request {
-            packed_events: vec![a_0.clone(), a_1.clone(), a_2_0.clone(), a_3_0.clone()],
-        };
+        let mut request =
+            Request::new(vec![a_0.clone(), a_1.clone(), a_2_0.clone(), a_3_0.clone()]);
         unwrap!(bob.handle_request(&alice_id, request.clone()));
 
         // [A_0, A_1, A_2,1, A_3,1] will be sent to Bob second.  Bob should accuse A_1.
@@ -1499,9 +3002,7 @@ mod handle_malice {
         let a_1 = unwrap!(nth_event(alice.graph(), 1).pack(alice.event_context()));
         let a_2_0 =
             PackedEvent::new_requesting(alice_id.clone(), bob_id.clone(), a_1.compute_hash());
-        let mut request = Request {
-            packed_events: vec![a_0.clone(), a_1.clone(), a_2_0.clone()],
-        };
+        let mut request = Request::new(vec![a_0.clone(), a_1.clone(), a_2_0.clone()]);
         unwrap!(bob.handle_request(&alice_id, request.clone()));
 
         // [A_0, A_1, A_2,1] will be sent to Carol.
@@ -1571,13 +3072,11 @@ mod handle_malice {
         }
 
         // Send Bob's message to Alice.  B_2 should be rejected as invalid.
-        let message = Request {
-            packed_events: vec![
-                unwrap!(b_0.pack(bob.event_context())),
-                unwrap!(b_1.pack(bob.event_context())),
-                b_2_packed.clone(),
-            ],
-        };
+        let message = Request::new(vec![
+            unwrap!(b_0.pack(bob.event_context())),
+            unwrap!(b_1.pack(bob.event_context())),
+            b_2_packed.clone(),
+        ]);
         assert_eq!(
             alice.handle_request(bob.our_pub_id(), message),
             Err(Error::InvalidEvent)
@@ -15,6 +15,7 @@ use super::{
 };
 use crate::{
     error::Error,
+    hash::Hash,
     id::{PublicId, SecretId},
     network_event::NetworkEvent,
     observation::{ObservationForStore, ObservationInfo},
@@ -36,15 +37,124 @@ use serde::{Deserialize, Serialize};
 pub(crate) enum Cause<V, E, P> {
     // Identifier of the latest `Event` of the peer which sent the request and the `PublicId` of the
     // intended recipient.
-    Requesting { self_parent: E, recipient: P },
+    Requesting {
+        self_parent: E,
+        recipient: P,
+    },
     // Identifiers of the latest `Event`s of own and the peer which sent the request.
-    Request { self_parent: E, other_parent: E },
+    Request {
+        self_parent: E,
+        other_parent: E,
+    },
     // Identifiers of the latest `Event`s of own and the peer which sent the response.
-    Response { self_parent: E, other_parent: E },
+    Response {
+        self_parent: E,
+        other_parent: E,
+    },
     // Identifier of our latest `Event`. Vote for a single network event.
-    Observation { self_parent: E, vote: V },
+    Observation {
+        self_parent: E,
+        vote: V,
+    },
     // Initial empty `Event` of this peer.
     Initial,
+    // Identifier of our latest `Event`. Share of the common coin identified by the round hash.
+    CoinShare {
+        self_parent: E,
+        round_hash: Hash,
+        share: Vec<u8>,
+    },
+}
+
+impl<V, E, P> Cause<V, E, P> {
+    pub(crate) fn as_ref(&self) -> Cause<&V, &E, &P> {
+        match *self {
+            Cause::Requesting {
+                ref self_parent,
+                ref recipient,
+            } => Cause::Requesting {
+                self_parent,
+                recipient,
+            },
+            Cause::Request {
+                ref self_parent,
+                ref other_parent,
+            } => Cause::Request {
+                self_parent,
+                other_parent,
+            },
+            Cause::Response {
+                ref self_parent,
+                ref other_parent,
+            } => Cause::Response {
+                self_parent,
+                other_parent,
+            },
+            Cause::Observation {
+                ref self_parent,
+                ref vote,
+            } => Cause::Observation { self_parent, vote },
+            Cause::Initial => Cause::Initial,
+            Cause::CoinShare {
+                ref self_parent,
+                round_hash,
+                ref share,
+            } => Cause::CoinShare {
+                self_parent,
+                round_hash,
+                share: share.clone(),
+            },
+        }
+    }
+
+    // Converts the vote, the parents and the recipient of this cause using the given functions.
+    // Returns `None` if any of the conversions fails.
+    pub(crate) fn map<V2, E2, P2>(
+        self,
+        map_vote: impl FnOnce(V) -> V2,
+        map_self_parent: impl FnOnce(E) -> Option<E2>,
+        map_other_parent: impl FnOnce(E) -> Option<E2>,
+        map_recipient: impl FnOnce(P) -> Option<P2>,
+    ) -> Option<Cause<V2, E2, P2>> {
+        let cause = match self {
+            Cause::Requesting {
+                self_parent,
+                recipient,
+            } => Cause::Requesting {
+                self_parent: map_self_parent(self_parent)?,
+                recipient: map_recipient(recipient)?,
+            },
+            Cause::Request {
+                self_parent,
+                other_parent,
+            } => Cause::Request {
+                self_parent: map_self_parent(self_parent)?,
+                other_parent: map_other_parent(other_parent)?,
+            },
+            Cause::Response {
+                self_parent,
+                other_parent,
+            } => Cause::Response {
+                self_parent: map_self_parent(self_parent)?,
+                other_parent: map_other_parent(other_parent)?,
+            },
+            Cause::Observation { self_parent, vote } => Cause::Observation {
+                self_parent: map_self_parent(self_parent)?,
+                vote: map_vote(vote),
+            },
+            Cause::Initial => Cause::Initial,
+            Cause::CoinShare {
+                self_parent,
+                round_hash,
+                share,
+            } => Cause::CoinShare {
+                self_parent: map_self_parent(self_parent)?,
+                round_hash,
+                share,
+            },
+        };
+        Some(cause)
+    }
 }
 
 impl<P: PublicId> Cause<VoteKey<P>, EventIndex, PeerIndex> {
@@ -87,7 +197,7 @@ impl<P: PublicId> Cause<VoteKey<P>, EventIndex, PeerIndex> {
             Cause::Observation { self_parent, vote } => {
                 let self_parent = self_parent_index(ctx.graph, &self_parent)?;
 
-                let (vote_key, observation) = VoteKey::new(vote, creator, ctx.consensus_mode);
+                let (vote_key, observation) = VoteKey::new(vote, creator, ctx.consensus_mode)?;
                 let payload_key = *vote_key.payload_key();
 
                 (
@@ -99,6 +209,18 @@ impl<P: PublicId> Cause<VoteKey<P>, EventIndex, PeerIndex> {
                 )
             }
             Cause::Initial => (Cause::Initial, None),
+            Cause::CoinShare {
+                self_parent,
+                round_hash,
+                share,
+            } => (
+                Cause::CoinShare {
+                    self_parent: self_parent_index(ctx.graph, &self_parent)?,
+                    round_hash,
+                    share,
+                },
+                None,
+            ),
         };
 
         Ok(cause)
@@ -138,6 +260,15 @@ impl<P: PublicId> Cause<VoteKey<P>, EventIndex, PeerIndex> {
                 vote: vote.resolve(ctx.observations)?,
             },
             Cause::Initial => Cause::Initial,
+            Cause::CoinShare {
+                self_parent,
+                round_hash,
+                ref share,
+            } => Cause::CoinShare {
+                self_parent: self_parent_hash(ctx.graph, self_parent)?,
+                round_hash,
+                share: share.clone(),
+            },
         };
         Ok(cause)
     }
@@ -209,7 +340,7 @@ impl Cause<VoteKey<PeerId>, EventIndex, PeerIndex> {
                 other_parent,
             },
             Cause::Observation { vote, .. } => {
-                let (vote_key, observation) = VoteKey::new(vote, creator, consensus_mode);
+                let (vote_key, observation) = unwrap!(VoteKey::new(vote, creator, consensus_mode));
                 let _ = observations
                     .entry(*vote_key.payload_key())
                     .or_insert_with(|| ObservationInfo::new(observation));
@@ -220,6 +351,13 @@ impl Cause<VoteKey<PeerId>, EventIndex, PeerIndex> {
                 }
             }
             Cause::Initial => Cause::Initial,
+            Cause::CoinShare {
+                round_hash, share, ..
+            } => Cause::CoinShare {
+                self_parent,
+                round_hash,
+                share,
+            },
         }
     }
 }
@@ -228,20 +366,14 @@ pub(super) fn self_parent_hash<P: PublicId>(
     graph: &Graph<P>,
     index: EventIndex,
 ) -> Result<EventHash, Error> {
-    graph
-        .get(index)
-        .map(|event| *event.hash())
-        .ok_or(Error::UnknownSelfParent)
+    graph.get_hash(index).ok_or(Error::UnknownSelfParent)
 }
 
 pub(super) fn other_parent_hash<P: PublicId>(
     graph: &Graph<P>,
     index: EventIndex,
 ) -> Result<EventHash, Error> {
-    graph
-        .get(index)
-        .map(|event| *event.hash())
-        .ok_or(Error::UnknownOtherParent)
+    graph.get_hash(index).ok_or(Error::UnknownOtherParent)
 }
 
 fn self_parent_index<P: PublicId>(graph: &Graph<P>, hash: &EventHash) -> Result<EventIndex, Error> {
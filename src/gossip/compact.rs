@@ -0,0 +1,229 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{cause::Cause, content::Content, event_hash::EventHash, packed_event::PackedEvent};
+use crate::{id::PublicId, network_event::NetworkEvent, vote::Vote};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Reference to a parent of an event of a `CompactMessage`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+enum ParentRef {
+    // The previous event of the same creator in the message. Only used for self-parents.
+    Implicit,
+    // The event at the given position in the message.
+    Position(u32),
+    // An event not included in the message.
+    Hash(EventHash),
+}
+
+#[serde(bound(
+    serialize = "V: Serialize, S: Serialize",
+    deserialize = "V: Deserialize<'de>, S: Deserialize<'de>"
+))]
+#[derive(Serialize, Deserialize)]
+struct CompactEvent<V, S> {
+    // Position of the creator in the peers of the message.
+    creator: u32,
+    // Cause with the recipient given by its position in the peers of the message.
+    cause: Cause<V, ParentRef, u32>,
+    signature: S,
+}
+
+// Wire representation of the events of a `Request` or a `Response`. Every creator and recipient
+// is sent once in the peer table of the message and then referenced by its position in it, and the
+// parents included in the same message are referenced by their position too, so the full ids and
+// hashes are only sent when needed. The receiver computes the hashes of the events again from their
+// canonical content, so their signatures stay valid.
+#[serde(bound(
+    serialize = "V: Serialize, S: Serialize, P: Serialize",
+    deserialize = "V: Deserialize<'de>, S: Deserialize<'de>, P: Deserialize<'de>"
+))]
+#[derive(Serialize, Deserialize)]
+pub(super) struct CompactMessage<V, S, P> {
+    peers: Vec<P>,
+    events: Vec<CompactEvent<V, S>>,
+    has_more: bool,
+}
+
+impl<'a, T: NetworkEvent, P: PublicId> CompactMessage<&'a Vote<T, P>, &'a P::Signature, &'a P> {
+    // Returns `None` if any of the causes of the events can't be converted.
+    pub fn new(packed_events: &'a [PackedEvent<T, P>], has_more: bool) -> Option<Self> {
+        let mut peers = Vec::new();
+        let mut peer_positions = BTreeMap::new();
+        let mut peer_position = |peer_id: &'a P| {
+            *peer_positions.entry(peer_id).or_insert_with(|| {
+                peers.push(peer_id);
+                peers.len() as u32 - 1
+            })
+        };
+
+        let mut events = Vec::with_capacity(packed_events.len());
+        let mut event_positions = BTreeMap::new();
+        let mut last_event_positions = BTreeMap::new();
+        for (position, packed_event) in packed_events.iter().enumerate() {
+            let position = position as u32;
+            let creator = &packed_event.content.creator;
+            let previous = last_event_positions.insert(creator, position);
+            let parent_ref = |hash: &EventHash| {
+                event_positions
+                    .get(hash)
+                    .map_or(ParentRef::Hash(*hash), |position| {
+                        ParentRef::Position(*position)
+                    })
+            };
+
+            let cause = packed_event.content.cause.as_ref().map(
+                |vote| vote,
+                |self_parent| match parent_ref(self_parent) {
+                    ParentRef::Position(position) if Some(position) == previous => {
+                        Some(ParentRef::Implicit)
+                    }
+                    parent => Some(parent),
+                },
+                |other_parent| Some(parent_ref(other_parent)),
+                |recipient| Some(peer_position(recipient)),
+            )?;
+            events.push(CompactEvent {
+                creator: peer_position(creator),
+                cause,
+                signature: &packed_event.signature,
+            });
+            let _ = event_positions.insert(packed_event.compute_hash(), position);
+        }
+
+        Some(Self {
+            peers,
+            events,
+            has_more,
+        })
+    }
+}
+
+impl<T: NetworkEvent, P: PublicId> CompactMessage<Vote<T, P>, P::Signature, P> {
+    // Returns the events of the message and whether more messages follow, or `None` if the
+    // message references peers or events it doesn't contain.
+    pub fn into_packed_events(self) -> Option<(Vec<PackedEvent<T, P>>, bool)> {
+        let peers = self.peers;
+        let peer = |position: u32| peers.get(position as usize).cloned();
+
+        let mut packed_events = Vec::with_capacity(self.events.len());
+        let mut hashes = Vec::with_capacity(self.events.len());
+        let mut last_event_hashes = BTreeMap::new();
+        for event in self.events {
+            let resolve = |parent_ref| match parent_ref {
+                ParentRef::Implicit => None,
+                ParentRef::Position(position) => hashes.get(position as usize).cloned(),
+                ParentRef::Hash(hash) => Some(hash),
+            };
+            let previous = last_event_hashes.get(&event.creator).cloned();
+
+            let cause = event.cause.map(
+                |vote| vote,
+                |self_parent| match self_parent {
+                    ParentRef::Implicit => previous,
+                    parent => resolve(parent),
+                },
+                resolve,
+                peer,
+            )?;
+            let packed_event = PackedEvent {
+                content: Content {
+                    creator: peer(event.creator)?,
+                    cause,
+                },
+                signature: event.signature,
+            };
+
+            let hash = packed_event.compute_hash();
+            hashes.push(hash);
+            let _ = last_event_hashes.insert(event.creator, hash);
+            packed_events.push(packed_event);
+        }
+
+        Some((packed_events, self.has_more))
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::{
+        mock::{self, PeerId, Transaction},
+        observation::Observation,
+    };
+    use maidsafe_utilities::serialisation;
+
+    type Message =
+        CompactMessage<Vote<Transaction, PeerId>, <PeerId as PublicId>::Signature, PeerId>;
+
+    fn create_events() -> Vec<PackedEvent<Transaction, PeerId>> {
+        let ids = mock::create_ids(2);
+        let alice = ids[0].clone();
+        let bob = ids[1].clone();
+
+        let a_0 = PackedEvent::new_initial(alice.clone());
+        let b_0 = PackedEvent::new_initial(bob.clone());
+        let a_1 = PackedEvent::new_requesting(alice.clone(), bob.clone(), a_0.compute_hash());
+        let b_1 = PackedEvent::new_request(bob.clone(), b_0.compute_hash(), a_1.compute_hash());
+        let b_2 = PackedEvent::new_observation(
+            bob,
+            b_1.compute_hash(),
+            Observation::OpaquePayload(Transaction::new("payload")),
+        );
+        let a_2 = PackedEvent::new_response(alice, a_1.compute_hash(), b_2.compute_hash());
+
+        // Leave `b_0` out, so `b_1` references its self-parent by hash.
+        vec![a_0, a_1, b_1, b_2, a_2]
+    }
+
+    #[test]
+    fn round_trip() {
+        let packed_events = create_events();
+        let serialised = unwrap!(serialisation::serialise(&unwrap!(CompactMessage::new(
+            &packed_events,
+            true
+        ))));
+        let message: Message = unwrap!(serialisation::deserialise(&serialised));
+
+        let (decoded, has_more) = unwrap!(message.into_packed_events());
+        assert!(has_more);
+        assert_eq!(decoded, packed_events);
+    }
+
+    #[test]
+    fn smaller_than_packed_events() {
+        let packed_events = create_events();
+        let compact = unwrap!(serialisation::serialise(&unwrap!(CompactMessage::new(
+            &packed_events,
+            false
+        ))));
+        let full = unwrap!(serialisation::serialise(&packed_events));
+        assert!(compact.len() < full.len());
+    }
+
+    #[test]
+    fn reject_unknown_references() {
+        let packed_events = create_events();
+        let serialised = unwrap!(serialisation::serialise(&unwrap!(CompactMessage::new(
+            &packed_events,
+            false
+        ))));
+
+        let mut message: Message = unwrap!(serialisation::deserialise(&serialised));
+        message.events[1].cause = Cause::Requesting {
+            self_parent: ParentRef::Position(3),
+            recipient: 1,
+        };
+        assert!(message.into_packed_events().is_none());
+
+        let mut message: Message = unwrap!(serialisation::deserialise(&serialised));
+        message.events[0].creator = 2;
+        assert!(message.into_packed_events().is_none());
+    }
+}
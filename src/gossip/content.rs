@@ -27,8 +27,8 @@ use serde::{Deserialize, Serialize};
 pub(super) struct Content<V, E, P> {
     // Identifier of the peer which created this `Event`.
     pub creator: P,
-    // Whether it was created by receiving a gossip request, response or by being given a network
-    // event to vote for.
+    // Whether it was created by receiving a gossip request, response, by being given a network
+    // event to vote for or by sharing a common coin.
     pub cause: Cause<V, E, P>,
 }
 
@@ -42,7 +42,10 @@ impl<V, E, P> Content<V, E, P> {
             | Cause::Response {
                 ref other_parent, ..
             } => Some(other_parent),
-            Cause::Requesting { .. } | Cause::Observation { .. } | Cause::Initial => None,
+            Cause::Requesting { .. }
+            | Cause::Observation { .. }
+            | Cause::Initial
+            | Cause::CoinShare { .. } => None,
         }
     }
 
@@ -61,6 +64,9 @@ impl<V, E, P> Content<V, E, P> {
             }
             | Cause::Observation {
                 ref self_parent, ..
+            }
+            | Cause::CoinShare {
+                ref self_parent, ..
             } => Some(self_parent),
             Cause::Initial => None,
         }
@@ -35,6 +35,8 @@ use crate::{
 use itertools::Itertools;
 use std::fmt::{self, Debug, Display, Formatter};
 
+#[serde(bound = "")]
+#[derive(Serialize, Deserialize)]
 pub(crate) struct Event<P: PublicId> {
     content: Content<VoteKey<P>, EventIndex, PeerIndex>,
     // Creator's signature of `content`.
@@ -144,8 +146,17 @@ impl<P: PublicId> Event<P> {
         observation: Observation<T, P>,
         ctx: EventContextRef<T, S>,
     ) -> Result<(Self, ObservationForStore<T, P>), Error> {
-        // Compute event hash + signature.
         let vote = Vote::new(ctx.peer_list.our_id(), observation);
+        Self::new_from_vote(self_parent, vote, ctx)
+    }
+
+    // Creates a new event carrying our vote.
+    pub fn new_from_vote<T: NetworkEvent, S: SecretId<PublicId = P>>(
+        self_parent: EventIndex,
+        vote: Vote<T, P>,
+        ctx: EventContextRef<T, S>,
+    ) -> Result<(Self, ObservationForStore<T, P>), Error> {
+        // Compute event hash + signature.
         let content = Content {
             creator: ctx.peer_list.our_pub_id().clone(),
             cause: Cause::Observation {
@@ -164,6 +175,41 @@ impl<P: PublicId> Event<P> {
         ))
     }
 
+    // Creates a new event carrying our share of the common coin identified by `round_hash`.
+    pub fn new_from_coin_share<T: NetworkEvent, S: SecretId<PublicId = P>>(
+        self_parent: EventIndex,
+        round_hash: Hash,
+        share: Vec<u8>,
+        ctx: EventContextRef<T, S>,
+    ) -> Result<Self, Error> {
+        let content: Content<Vote<T, _>, _, _> = Content {
+            creator: ctx.peer_list.our_pub_id().clone(),
+            cause: Cause::CoinShare {
+                self_parent: cause::self_parent_hash(ctx.graph, self_parent)?,
+                round_hash,
+                share: share.clone(),
+            },
+        };
+        let (hash, signature) = compute_event_hash_and_signature(&content, ctx.peer_list.our_id());
+
+        let content = Content {
+            creator: PeerIndex::OUR,
+            cause: Cause::CoinShare {
+                self_parent,
+                round_hash,
+                share,
+            },
+        };
+
+        Ok(Self::new(
+            hash,
+            signature,
+            content,
+            ctx.graph,
+            ctx.peer_list,
+        ))
+    }
+
     // Creates an initial event.  This is the first event by its creator in the graph.
     pub fn new_initial<T: NetworkEvent, S: SecretId<PublicId = P>>(
         ctx: EventContextRef<T, S>,
@@ -208,9 +254,10 @@ impl<P: PublicId> Event<P> {
         packed_event: PackedEvent<T, P>,
         ctx: EventContextRef<T, S>,
     ) -> Result<Option<UnpackedEvent<T, P>>, Error> {
-        let hash = compute_event_hash_and_verify_signature(
+        let (hash, signing_key) = compute_event_hash_and_verify_signature(
             &packed_event.content,
             &packed_event.signature,
+            ctx.peer_list,
         )?;
 
         if ctx.graph.contains(&hash) {
@@ -222,6 +269,16 @@ impl<P: PublicId> Event<P> {
         let (content, observation_for_store) = Content::unpack(packed_event.content, ctx)?;
         let cache = Cache::new(hash, &content, graph, peer_list);
 
+        // The creator doesn't sign with an id it already rotated its key from.
+        if let Some(key) = signing_key {
+            let can_sign = peer_list.get(content.creator).map_or(false, |creator| {
+                creator.can_sign_with(key, cache.index_by_creator)
+            });
+            if !can_sign {
+                return Err(Error::SignatureFailure);
+            }
+        }
+
         Ok(Some(UnpackedEvent {
             event: Self {
                 content,
@@ -229,9 +286,27 @@ impl<P: PublicId> Event<P> {
                 cache,
             },
             observation_for_store,
+            signing_key,
         }))
     }
 
+    // Verifies that `packed_event` is signed by its creator, which must be one of our peers,
+    // without unpacking it, which needs its parents.
+    pub fn verify_packed_signature<T: NetworkEvent, S: SecretId<PublicId = P>>(
+        packed_event: &PackedEvent<T, P>,
+        peer_list: &PeerList<S>,
+    ) -> Result<(), Error> {
+        if peer_list.get_index(packed_event.creator()).is_none() {
+            return Err(Error::UnknownPeer);
+        }
+        compute_event_hash_and_verify_signature(
+            &packed_event.content,
+            &packed_event.signature,
+            peer_list,
+        )
+        .map(|_| ())
+    }
+
     // Creates a `PackedEvent` from this `Event`.
     pub fn pack<T: NetworkEvent, S: SecretId<PublicId = P>>(
         &self,
@@ -312,6 +387,15 @@ impl<P: PublicId> Event<P> {
         }
     }
 
+    // Returns the expiry of the vote carried by this event, if any. For more details, see
+    // `Vote::expiry`.
+    pub fn vote_expiry(&self) -> Option<usize> {
+        match self.content.cause {
+            Cause::Observation { ref vote, .. } => vote.expiry(),
+            _ => None,
+        }
+    }
+
     pub fn vote_and_payload_key<T: NetworkEvent>(
         &self,
         observations: &ObservationStore<T, P>,
@@ -327,6 +411,19 @@ impl<P: PublicId> Event<P> {
         }
     }
 
+    // Returns the share of the common coin identified by `round_hash` carried by this event, if
+    // any.
+    pub fn coin_share(&self, round_hash: &Hash) -> Option<&[u8]> {
+        match self.content.cause {
+            Cause::CoinShare {
+                round_hash: ref share_round_hash,
+                ref share,
+                ..
+            } if share_round_hash == round_hash => Some(share),
+            _ => None,
+        }
+    }
+
     pub fn creator(&self) -> PeerIndex {
         self.content.creator
     }
@@ -375,7 +472,7 @@ impl<P: PublicId> Event<P> {
     pub fn is_sync_event(&self) -> bool {
         match self.content.cause {
             Cause::Requesting { .. } | Cause::Request { .. } | Cause::Response { .. } => true,
-            Cause::Initial | Cause::Observation { .. } => false,
+            Cause::Initial | Cause::Observation { .. } | Cause::CoinShare { .. } => false,
         }
     }
 
@@ -412,6 +509,15 @@ impl<P: PublicId> Event<P> {
         }
     }
 
+    #[cfg(test)]
+    pub fn is_coin_share(&self) -> bool {
+        if let Cause::CoinShare { .. } = self.content.cause {
+            true
+        } else {
+            false
+        }
+    }
+
     pub fn is_initial(&self) -> bool {
         if let Cause::Initial = self.content.cause {
             true
@@ -570,6 +676,8 @@ impl Event<PeerId> {
 pub(crate) struct UnpackedEvent<T: NetworkEvent, P: PublicId> {
     pub event: Event<P>,
     pub observation_for_store: ObservationForStore<T, P>,
+    // Position of the id the event was signed with among the ids of its creator, if known.
+    pub signing_key: Option<usize>,
 }
 
 #[cfg(any(test, feature = "testing"))]
@@ -584,6 +692,7 @@ pub(crate) enum CauseInput {
 }
 
 // Properties of `Event` that can be computed from its `Content`.
+#[derive(Serialize, Deserialize)]
 struct Cache {
     // Hash of `Event`s `Content`.
     hash: EventHash,
@@ -654,16 +763,33 @@ fn compute_event_hash_and_signature<T: NetworkEvent, S: SecretId>(
     (hash, signature)
 }
 
-fn compute_event_hash_and_verify_signature<T: NetworkEvent, P: PublicId>(
-    content: &Content<Vote<T, P>, EventHash, P>,
-    signature: &P::Signature,
-) -> Result<EventHash, Error> {
+// The creator might have rotated its key, so the signature is checked against all the ids it
+// rotated to by consensus. Also returns the position of the signing id among them, if the creator
+// is known.
+fn compute_event_hash_and_verify_signature<T: NetworkEvent, S: SecretId>(
+    content: &Content<Vote<T, S::PublicId>, EventHash, S::PublicId>,
+    signature: &<S::PublicId as PublicId>::Signature,
+    peer_list: &PeerList<S>,
+) -> Result<(EventHash, Option<usize>), Error> {
     let serialised_content = serialise(content);
-    if content
-        .creator
-        .verify_signature(signature, &serialised_content)
+    let (is_valid, signing_key) = if let Some(creator) = peer_list
+        .get_index(&content.creator)
+        .and_then(|index| peer_list.get(index))
     {
-        Ok(EventHash(Hash::from(serialised_content.as_slice())))
+        let signing_key = creator.signing_key(signature, &serialised_content);
+        (signing_key.is_some(), signing_key)
+    } else {
+        let is_valid = content
+            .creator
+            .verify_signature(signature, &serialised_content);
+        (is_valid, None)
+    };
+
+    if is_valid {
+        Ok((
+            EventHash(Hash::from(serialised_content.as_slice())),
+            signing_key,
+        ))
     } else {
         Err(Error::SignatureFailure)
     }
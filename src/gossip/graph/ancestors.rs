@@ -30,10 +30,11 @@ impl<'a, P: PublicId> Iterator for Ancestors<'a, P> {
             let event = *self.queue.iter().rev().next()?;
             let _ = self.queue.remove(&event);
 
-            if self.visited[event.topological_index()] {
+            let position = event.topological_index() - self.graph.start_index();
+            if self.visited[position] {
                 continue;
             }
-            self.visited[event.topological_index()] = true;
+            self.visited[position] = true;
 
             if let Some(parent) = event.self_parent().and_then(|index| self.graph.get(index)) {
                 let _ = self.queue.insert(parent);
@@ -16,16 +16,35 @@ use super::{event::Event, event_hash::EventHash};
 use crate::id::PublicId;
 #[cfg(feature = "malice-detection")]
 use fnv::FnvHashSet;
-use std::collections::{
-    btree_map::{BTreeMap, Entry},
-    BTreeSet,
+use std::{
+    cmp,
+    collections::{
+        btree_map::{BTreeMap, Entry},
+        BTreeSet,
+    },
+    mem,
 };
 
+// Index of the first event of a graph starting from a checkpoint.
+const CHECKPOINT_START_INDEX: usize = usize::MAX / 2;
+
 /// The gossip graph.
-#[derive(Eq, PartialEq, Debug)]
+#[serde(bound = "")]
+#[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub(crate) struct Graph<P: PublicId> {
     events: Vec<Event<P>>,
     indices: BTreeMap<EventHash, EventIndex>,
+    /// Topological index of the first event in `events`. Non-zero only after pruning.
+    start_index: usize,
+    /// Hashes of pruned events which are still parents of some retained events. Needed to pack
+    /// those retained events.
+    pruned_parents: BTreeMap<EventIndex, EventHash>,
+    /// Whether this graph started from a checkpoint and is still receiving the first events
+    /// following it, whose parents preceding the checkpoint are never received.
+    catching_up_from_checkpoint: bool,
+    /// Hashes of the events preceding the checkpoint this graph started from which its signers
+    /// committed to as the possible parents of the events following it.
+    checkpoint_frontier: BTreeSet<EventHash>,
     /// Indices of `Requesting` events with no associated descendant `Request`, and `Request`s with
     /// no associated descendant `Response`.
     #[cfg(feature = "malice-detection")]
@@ -37,6 +56,10 @@ impl<P: PublicId> Default for Graph<P> {
         Self {
             events: Vec::new(),
             indices: BTreeMap::new(),
+            start_index: 0,
+            pruned_parents: BTreeMap::new(),
+            catching_up_from_checkpoint: false,
+            checkpoint_frontier: BTreeSet::new(),
             #[cfg(feature = "malice-detection")]
             awaiting_associated_events: FnvHashSet::default(),
         }
@@ -48,6 +71,54 @@ impl<P: PublicId> Graph<P> {
         Self::default()
     }
 
+    /// Creates an empty graph starting from a checkpoint. The events following the checkpoint
+    /// have parents which are never received, so their hashes are recorded as pruned using
+    /// `insert_checkpoint_parent` while catching up, provided they are in `frontier`.
+    pub fn new_from_checkpoint(frontier: BTreeSet<EventHash>) -> Self {
+        Self {
+            // Leave room below the first event for the indices of the parents preceding the
+            // checkpoint.
+            start_index: CHECKPOINT_START_INDEX,
+            catching_up_from_checkpoint: true,
+            checkpoint_frontier: frontier,
+            ..Self::default()
+        }
+    }
+
+    /// Whether this graph started from a checkpoint and is still catching up from it.
+    pub fn is_catching_up_from_checkpoint(&self) -> bool {
+        self.catching_up_from_checkpoint
+    }
+
+    /// Stops accepting events with unknown parents as following the checkpoint.
+    pub fn finish_catching_up_from_checkpoint(&mut self) {
+        self.catching_up_from_checkpoint = false;
+        self.checkpoint_frontier.clear();
+    }
+
+    /// Records `hash` as the hash of a pruned event preceding the checkpoint this graph started
+    /// from, so events having it as parent can be inserted. Returns its index, or `None` if the
+    /// checkpoint's frontier doesn't contain it or there is no room left for it.
+    pub fn insert_checkpoint_parent(&mut self, hash: EventHash) -> Option<EventIndex> {
+        if let Some(index) = self.get_index(&hash) {
+            return Some(index);
+        }
+        if !self.checkpoint_frontier.contains(&hash) {
+            return None;
+        }
+
+        let index = self
+            .pruned_parents
+            .keys()
+            .next()
+            .map_or(self.start_index, |index| index.0)
+            .checked_sub(1)
+            .map(EventIndex)?;
+        let _ = self.pruned_parents.insert(index, hash);
+        let _ = self.indices.insert(hash, index);
+        Some(index)
+    }
+
     /// Get index of an event with the given hash.
     pub fn get_index(&self, hash: &EventHash) -> Option<EventIndex> {
         self.indices.get(hash).cloned()
@@ -73,7 +144,7 @@ impl<P: PublicId> Graph<P> {
         let index = match self.indices.entry(*event.hash()) {
             Entry::Occupied(entry) => *entry.get(),
             Entry::Vacant(entry) => {
-                let index = EventIndex(self.events.len());
+                let index = EventIndex(self.start_index + self.events.len());
 
                 #[cfg(any(test, feature = "testing"))]
                 assert_ne!(index, EventIndex::PHONY);
@@ -90,17 +161,27 @@ impl<P: PublicId> Graph<P> {
 
         IndexedEventRef {
             index,
-            event: &self.events[index.0],
+            event: &self.events[index.0 - self.start_index],
         }
     }
 
-    /// Gets `Event` with the given `index`, if it exists.
+    /// Gets `Event` with the given `index`, if it exists (and hasn't been pruned).
     pub fn get(&self, index: EventIndex) -> Option<IndexedEventRef<P>> {
-        self.events
-            .get(index.0)
+        index
+            .0
+            .checked_sub(self.start_index)
+            .and_then(|position| self.events.get(position))
             .map(|event| IndexedEventRef { index, event })
     }
 
+    /// Gets hash of the event with the given `index`. Unlike `get`, this also works for pruned
+    /// events which are parents of events still in the graph.
+    pub fn get_hash(&self, index: EventIndex) -> Option<EventHash> {
+        self.get(index)
+            .map(|event| *event.hash())
+            .or_else(|| self.pruned_parents.get(&index).cloned())
+    }
+
     /// Gets `Event` by the given `hash`, if it exists.
     pub fn get_by_hash<'a>(&'a self, hash: &EventHash) -> Option<IndexedEventRef<'a, P>> {
         self.get_index(hash).and_then(|index| self.get(index))
@@ -111,22 +192,85 @@ impl<P: PublicId> Graph<P> {
         self.events.len()
     }
 
+    /// Topological index of the first event in this graph. Non-zero only if the graph has been
+    /// pruned.
+    pub fn start_index(&self) -> usize {
+        self.start_index
+    }
+
+    /// Topological index one past the last event in this graph (that is, the index the next
+    /// inserted event will get).
+    pub fn end_index(&self) -> usize {
+        self.start_index + self.events.len()
+    }
+
     /// Iterator over all events in this graph. Yields `IndexedEventRef`s.
     pub fn iter(&self) -> Iter<P> {
-        self.iter_from(0)
+        self.iter_from(self.start_index)
     }
 
     /// Iterator over events in this graph starting at the given topological index.
     pub fn iter_from(&self, start_index: usize) -> Iter<P> {
         Iter {
             events: &self.events,
-            index: start_index,
+            offset: self.start_index,
+            index: cmp::max(start_index, self.start_index),
         }
     }
 
     /// Iterator over event indices starting at the given topological index.
     pub fn indices_from(&self, start_index: usize) -> impl Iterator<Item = EventIndex> {
-        (start_index..self.events.len()).map(EventIndex)
+        (cmp::max(start_index, self.start_index)..self.end_index()).map(EventIndex)
+    }
+
+    /// Discards all events whose topological index is less than `end_index`. Returns the number of
+    /// discarded events.
+    ///
+    /// The indices of the remaining events do not change. Looking up a discarded event returns
+    /// `None`, except for `get_hash` which still works for discarded events that are parents of
+    /// remaining ones.
+    pub fn prune(&mut self, end_index: usize) -> usize {
+        let end_index = cmp::min(end_index, self.end_index());
+        if end_index <= self.start_index {
+            return 0;
+        }
+
+        let start_index = self.start_index;
+        let pruned: Vec<_> = self
+            .events
+            .drain(..end_index - start_index)
+            .enumerate()
+            .map(|(position, event)| (EventIndex(start_index + position), *event.hash()))
+            .collect();
+        let mut pruned_parents = mem::replace(&mut self.pruned_parents, BTreeMap::new());
+        pruned_parents.extend(pruned.iter().cloned());
+        for (_, hash) in &pruned {
+            let _ = self.indices.remove(hash);
+        }
+        self.start_index = end_index;
+
+        #[cfg(feature = "malice-detection")]
+        self.awaiting_associated_events
+            .retain(|index| index.0 >= end_index);
+
+        // Keep only the hashes which retained events still refer to.
+        for event in &self.events {
+            for parent in event.self_parent().into_iter().chain(event.other_parent()) {
+                if parent.0 < end_index {
+                    if let Some(hash) = pruned_parents.get(&parent) {
+                        let _ = self.pruned_parents.insert(parent, *hash);
+                    }
+                }
+            }
+        }
+        // The parents preceding our checkpoint are indexed too. Forget the ones no longer needed.
+        for (index, hash) in pruned_parents {
+            if !self.pruned_parents.contains_key(&index) {
+                let _ = self.indices.remove(&hash);
+            }
+        }
+
+        pruned.len()
     }
 
     /// Returns self-parent of the given event, if any.
@@ -178,7 +322,7 @@ impl<P: PublicId> Graph<P> {
         Ancestors {
             graph: self,
             queue,
-            visited: vec![false; event.topological_index() + 1],
+            visited: vec![false; event.topological_index() + 1 - self.start_index],
         }
     }
 }
@@ -193,42 +337,46 @@ impl<P: PublicId> Graph<P> {
 
     /// Returns `Some(true)` if the event is a `Request` or `Response` and is valid (follows the
     /// `Requesting -> Request -> Response` pattern).  Returns `Some(false)` if the event is a
-    /// `Request` or `Response` and is invalid.  Otherwise returns `None`.
+    /// `Request` or `Response` and is invalid.  Otherwise returns `None`, including when the events
+    /// of the pattern were pruned or precede the checkpoint we started from.
     pub fn is_valid_sync_event(&self, event: &Event<P>) -> Option<bool> {
-        if event.is_request() {
-            Some(
-                self.other_parent(event)
-                    .map(|requesting_event| {
-                        Some(event.creator()) == requesting_event.requesting_recipient()
-                            && self.is_awaiting_associated_event(requesting_event)
-                    })
-                    .unwrap_or(false),
-            )
+        let is_valid = if event.is_request() {
+            self.other_parent(event).map(|requesting_event| {
+                Some(event.creator()) == requesting_event.requesting_recipient()
+                    && self.is_awaiting_associated_event(requesting_event)
+            })
         } else if event.is_response() {
-            Some(
-                self.other_parent(event)
-                    .and_then(|other_parent| self.self_sync_ancestor(other_parent))
-                    .and_then(|request_event| {
-                        self.other_parent(request_event)
-                            .map(|requesting_event| (request_event, requesting_event))
-                    })
-                    .map(|(request_event, requesting_event)| {
-                        request_event.is_request()
-                            && requesting_event.creator() == event.creator()
-                            && self.is_awaiting_associated_event(request_event)
-                    })
-                    .unwrap_or(false),
-            )
+            self.other_parent(event)
+                .and_then(|other_parent| self.self_sync_ancestor(other_parent))
+                .and_then(|request_event| {
+                    self.other_parent(request_event)
+                        .map(|requesting_event| (request_event, requesting_event))
+                })
+                .map(|(request_event, requesting_event)| {
+                    request_event.is_request()
+                        && requesting_event.creator() == event.creator()
+                        && self.is_awaiting_associated_event(request_event)
+                })
         } else {
-            None
-        }
+            return None;
+        };
+
+        // Without pruned parents, failing to find the events of the pattern means they don't
+        // exist.
+        is_valid.or_else(|| {
+            if self.pruned_parents.is_empty() {
+                Some(false)
+            } else {
+                None
+            }
+        })
     }
 
     fn awaiting_and_awaited_indices(
         &self,
         index: EventIndex,
     ) -> (Option<EventIndex>, Option<EventIndex>) {
-        let event = &self.events[index.0];
+        let event = &self.events[index.0 - self.start_index];
         if event.is_requesting() {
             (Some(index), None)
         } else if event.is_request() {
@@ -260,7 +408,7 @@ impl<P: PublicId> Graph<P> {
 impl<P: PublicId> Graph<P> {
     /// Remove the topologically last event.
     pub fn remove_last(&mut self) -> Option<(EventIndex, Event<P>)> {
-        let index = EventIndex(self.events.len() - 1);
+        let index = EventIndex(self.end_index().checked_sub(1)?);
         #[cfg(feature = "malice-detection")]
         {
             let (awaiting, awaited) = self.awaiting_and_awaited_indices(index);
@@ -305,7 +453,10 @@ impl<P: PublicId> IntoIterator for Graph<P> {
         let mut events = self.events;
         events.reverse();
 
-        IntoIter { events, index: 0 }
+        IntoIter {
+            events,
+            index: self.start_index,
+        }
     }
 }
 
@@ -333,15 +484,13 @@ impl<'a, P: PublicId> IntoIterator for &'a Graph<P> {
     type Item = <Self::IntoIter as Iterator>::Item;
 
     fn into_iter(self) -> Self::IntoIter {
-        Iter {
-            events: &self.events,
-            index: 0,
-        }
+        self.iter()
     }
 }
 
 pub(crate) struct Iter<'a, P: PublicId + 'a> {
     events: &'a [Event<P>],
+    offset: usize,
     index: usize,
 }
 
@@ -349,7 +498,7 @@ impl<'a, P: PublicId> Iterator for Iter<'a, P> {
     type Item = IndexedEventRef<'a, P>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let event = self.events.get(self.index)?;
+        let event = self.events.get(self.index - self.offset)?;
         let item = IndexedEventRef {
             index: EventIndex(self.index),
             event,
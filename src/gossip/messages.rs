@@ -6,30 +6,391 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{gossip::packed_event::PackedEvent, id::PublicId, network_event::NetworkEvent};
+use crate::{
+    gossip::{compact::CompactMessage, event_hash::EventHash, packed_event::PackedEvent},
+    id::PublicId,
+    network_event::NetworkEvent,
+    serialise,
+};
+use maidsafe_utilities::serialisation;
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use std::{collections::VecDeque, mem};
+
+/// Version of the gossip protocol implemented by this crate. It is increased whenever the format
+/// of the gossip messages changes.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest version of the gossip protocol whose messages this crate can still read and write.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
 
 /// A gossip request message.
-#[serde(bound = "")]
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Request<T: NetworkEvent, P: PublicId> {
     pub(crate) packed_events: Vec<PackedEvent<T, P>>,
+    // True if the request is split over several messages and this isn't the last one.
+    pub(crate) has_more: bool,
+    // Version of the gossip protocol the request is written in.
+    pub(crate) version: u32,
+    // Newest version of the gossip protocol supported by the sender.
+    pub(crate) max_version: u32,
+    // Body of the message if it's written in a version we don't support, kept as received so the
+    // message can be serialised again.
+    pub(crate) undecoded_body: Vec<u8>,
 }
 
 impl<T: NetworkEvent, P: PublicId> Request<T, P> {
     pub(crate) fn new(packed_events: Vec<PackedEvent<T, P>>) -> Self {
-        Self { packed_events }
+        Self::new_chunk(packed_events, false)
+    }
+
+    pub(crate) fn new_chunk(packed_events: Vec<PackedEvent<T, P>>, has_more: bool) -> Self {
+        Self {
+            packed_events,
+            has_more,
+            version: PROTOCOL_VERSION,
+            max_version: PROTOCOL_VERSION,
+            undecoded_body: vec![],
+        }
+    }
+
+    /// Returns the version of the gossip protocol this request is written in.
+    pub fn version(&self) -> u32 {
+        self.version
     }
 }
 
 /// A gossip response message.
-#[serde(bound = "")]
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Response<T: NetworkEvent, P: PublicId> {
     pub(crate) packed_events: Vec<PackedEvent<T, P>>,
+    // True if the response is split over several messages and this isn't the last one.
+    pub(crate) has_more: bool,
+    // Version of the gossip protocol the response is written in.
+    pub(crate) version: u32,
+    // Newest version of the gossip protocol supported by the sender.
+    pub(crate) max_version: u32,
+    // Body of the message if it's written in a version we don't support, kept as received so the
+    // message can be serialised again.
+    pub(crate) undecoded_body: Vec<u8>,
 }
 
 impl<T: NetworkEvent, P: PublicId> Response<T, P> {
     pub(crate) fn new(packed_events: Vec<PackedEvent<T, P>>) -> Self {
-        Self { packed_events }
+        Self::new_chunk(packed_events, false)
+    }
+
+    pub(crate) fn new_chunk(packed_events: Vec<PackedEvent<T, P>>, has_more: bool) -> Self {
+        Self {
+            packed_events,
+            has_more,
+            version: PROTOCOL_VERSION,
+            max_version: PROTOCOL_VERSION,
+            undecoded_body: vec![],
+        }
+    }
+
+    /// Returns the version of the gossip protocol this response is written in.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+/// A message asking a peer for the gossip events with the given hashes, which the sender is
+/// missing to handle the last gossip message it received from that peer.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Fetch {
+    pub(crate) hashes: Vec<EventHash>,
+    // Version of the gossip protocol the fetch is written in.
+    pub(crate) version: u32,
+    // Newest version of the gossip protocol supported by the sender.
+    pub(crate) max_version: u32,
+    // Body of the message if it's written in a version we don't support, kept as received so the
+    // message can be serialised again.
+    pub(crate) undecoded_body: Vec<u8>,
+}
+
+impl Fetch {
+    pub(crate) fn new(hashes: Vec<EventHash>) -> Self {
+        Self {
+            hashes,
+            version: PROTOCOL_VERSION,
+            max_version: PROTOCOL_VERSION,
+            undecoded_body: vec![],
+        }
+    }
+
+    /// Returns the hashes of the requested events.
+    pub fn hashes(&self) -> &[EventHash] {
+        &self.hashes
+    }
+
+    /// Returns the version of the gossip protocol this fetch is written in.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+/// A part of the reply to a [Fetch](struct.Fetch.html), carrying some of the requested events
+/// and their ancestors.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FetchResponse<T: NetworkEvent, P: PublicId> {
+    pub(crate) packed_events: Vec<PackedEvent<T, P>>,
+    // Version of the gossip protocol the response is written in.
+    pub(crate) version: u32,
+    // Newest version of the gossip protocol supported by the sender.
+    pub(crate) max_version: u32,
+    // Body of the message if it's written in a version we don't support, kept as received so the
+    // message can be serialised again.
+    pub(crate) undecoded_body: Vec<u8>,
+}
+
+impl<T: NetworkEvent, P: PublicId> FetchResponse<T, P> {
+    pub(crate) fn new(packed_events: Vec<PackedEvent<T, P>>) -> Self {
+        Self {
+            packed_events,
+            version: PROTOCOL_VERSION,
+            max_version: PROTOCOL_VERSION,
+            undecoded_body: vec![],
+        }
+    }
+
+    /// Returns the version of the gossip protocol this response is written in.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+pub(crate) fn is_supported_version(version: u32) -> bool {
+    version >= MIN_PROTOCOL_VERSION && version <= PROTOCOL_VERSION
+}
+
+// Gossip messages are serialised as their protocol versions followed by the body of the message
+// written in that version. The body of a message in a version we don't support is left undecoded,
+// so handling the message fails with `Error::IncompatibleVersion` rather than it failing to
+// deserialise.
+#[derive(Serialize, Deserialize)]
+struct VersionedMessage {
+    version: u32,
+    max_version: u32,
+    body: Vec<u8>,
+}
+
+impl VersionedMessage {
+    // Writes the body using `encode_body` if `version` is supported, or keeps `undecoded_body`
+    // otherwise.
+    fn new<F>(
+        version: u32,
+        max_version: u32,
+        encode_body: F,
+        undecoded_body: &[u8],
+    ) -> Result<Self, String>
+    where
+        F: FnOnce() -> Result<Vec<u8>, String>,
+    {
+        let body = if is_supported_version(version) {
+            encode_body()?
+        } else {
+            undecoded_body.to_vec()
+        };
+
+        Ok(Self {
+            version,
+            max_version,
+            body,
+        })
+    }
+
+    // Reads the body using `decode_body` if the version is supported. Returns it, or its default
+    // value together with the body left undecoded otherwise.
+    fn decode_with<B, F>(self, decode_body: F) -> Result<(B, Vec<u8>), String>
+    where
+        B: Default,
+        F: FnOnce(&[u8]) -> Result<B, String>,
+    {
+        if is_supported_version(self.version) {
+            Ok((decode_body(&self.body)?, vec![]))
+        } else {
+            Ok((B::default(), self.body))
+        }
+    }
+
+    fn encode<T: NetworkEvent, P: PublicId>(
+        version: u32,
+        max_version: u32,
+        packed_events: &[PackedEvent<T, P>],
+        has_more: bool,
+        undecoded_body: &[u8],
+    ) -> Result<Self, String> {
+        Self::new(
+            version,
+            max_version,
+            || {
+                // Version 1 is the compact format. See `CompactMessage`.
+                let message = CompactMessage::new(packed_events, has_more)
+                    .ok_or_else(|| "invalid gossip event".to_string())?;
+                serialisation::serialise(&message).map_err(|error| error.to_string())
+            },
+            undecoded_body,
+        )
+    }
+
+    // Returns the events of the message, whether more messages follow, and the body if it's left
+    // undecoded.
+    fn decode<T: NetworkEvent, P: PublicId>(
+        self,
+    ) -> Result<(Vec<PackedEvent<T, P>>, bool, Vec<u8>), String> {
+        let ((packed_events, has_more), undecoded_body) = self.decode_with(|body| {
+            let message: CompactMessage<_, _, _> =
+                serialisation::deserialise(body).map_err(|error| error.to_string())?;
+            message
+                .into_packed_events()
+                .ok_or_else(|| "invalid reference in compact gossip message".to_string())
+        })?;
+        Ok((packed_events, has_more, undecoded_body))
     }
 }
+
+impl<T: NetworkEvent, P: PublicId> Serialize for Request<T, P> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        VersionedMessage::encode(
+            self.version,
+            self.max_version,
+            &self.packed_events,
+            self.has_more,
+            &self.undecoded_body,
+        )
+        .map_err(ser::Error::custom)?
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T: NetworkEvent, P: PublicId> Deserialize<'de> for Request<T, P> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let message = VersionedMessage::deserialize(deserializer)?;
+        let (version, max_version) = (message.version, message.max_version);
+        let (packed_events, has_more, undecoded_body) =
+            message.decode().map_err(de::Error::custom)?;
+        Ok(Self {
+            packed_events,
+            has_more,
+            version,
+            max_version,
+            undecoded_body,
+        })
+    }
+}
+
+impl<T: NetworkEvent, P: PublicId> Serialize for Response<T, P> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        VersionedMessage::encode(
+            self.version,
+            self.max_version,
+            &self.packed_events,
+            self.has_more,
+            &self.undecoded_body,
+        )
+        .map_err(ser::Error::custom)?
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T: NetworkEvent, P: PublicId> Deserialize<'de> for Response<T, P> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let message = VersionedMessage::deserialize(deserializer)?;
+        let (version, max_version) = (message.version, message.max_version);
+        let (packed_events, has_more, undecoded_body) =
+            message.decode().map_err(de::Error::custom)?;
+        Ok(Self {
+            packed_events,
+            has_more,
+            version,
+            max_version,
+            undecoded_body,
+        })
+    }
+}
+
+impl Serialize for Fetch {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        VersionedMessage::new(
+            self.version,
+            self.max_version,
+            || serialisation::serialise(&self.hashes).map_err(|error| error.to_string()),
+            &self.undecoded_body,
+        )
+        .map_err(ser::Error::custom)?
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Fetch {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let message = VersionedMessage::deserialize(deserializer)?;
+        let (version, max_version) = (message.version, message.max_version);
+        let (hashes, undecoded_body) = message
+            .decode_with(|body| serialisation::deserialise(body).map_err(|error| error.to_string()))
+            .map_err(de::Error::custom)?;
+        Ok(Self {
+            hashes,
+            version,
+            max_version,
+            undecoded_body,
+        })
+    }
+}
+
+impl<T: NetworkEvent, P: PublicId> Serialize for FetchResponse<T, P> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        VersionedMessage::encode(
+            self.version,
+            self.max_version,
+            &self.packed_events,
+            false,
+            &self.undecoded_body,
+        )
+        .map_err(ser::Error::custom)?
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T: NetworkEvent, P: PublicId> Deserialize<'de> for FetchResponse<T, P> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let message = VersionedMessage::deserialize(deserializer)?;
+        let (version, max_version) = (message.version, message.max_version);
+        let (packed_events, _, undecoded_body) = message.decode().map_err(de::Error::custom)?;
+        Ok(Self {
+            packed_events,
+            version,
+            max_version,
+            undecoded_body,
+        })
+    }
+}
+
+// Splits `packed_events` into consecutive chunks, each of which has a total serialised size of at
+// most `max_size`, unless it consists of a single event bigger than that.
+pub(crate) fn split_into_chunks<T: NetworkEvent, P: PublicId>(
+    packed_events: Vec<PackedEvent<T, P>>,
+    max_size: usize,
+) -> VecDeque<Vec<PackedEvent<T, P>>> {
+    let mut chunks = VecDeque::new();
+    let mut chunk = Vec::new();
+    let mut chunk_size = 0;
+
+    for packed_event in packed_events {
+        let size = serialise(&packed_event).len();
+        if !chunk.is_empty() && chunk_size + size > max_size {
+            chunks.push_back(mem::replace(&mut chunk, Vec::new()));
+            chunk_size = 0;
+        }
+
+        chunk_size += size;
+        chunk.push(packed_event);
+    }
+
+    if !chunk.is_empty() {
+        chunks.push_back(chunk);
+    }
+
+    chunks
+}
@@ -8,6 +8,7 @@
 
 mod abstract_event;
 mod cause;
+mod compact;
 mod content;
 mod event;
 mod event_context;
@@ -16,6 +17,7 @@ mod event_utils;
 mod graph;
 mod messages;
 mod packed_event;
+mod strategy;
 
 #[cfg(any(test, feature = "testing", feature = "dump-graphs"))]
 pub(super) use self::cause::Cause;
@@ -28,9 +30,11 @@ pub(super) use self::{
     event::Event,
     event_context::EventContextRef,
     graph::{EventIndex, Graph, IndexedEventRef},
+    messages::{is_supported_version, split_into_chunks},
 };
 pub use self::{
     event_hash::EventHash,
-    messages::{Request, Response},
+    messages::{Fetch, FetchResponse, Request, Response, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION},
     packed_event::PackedEvent,
+    strategy::GossipStrategy,
 };
@@ -42,6 +42,18 @@ impl<T: NetworkEvent, P: PublicId> PackedEvent<T, P> {
     pub(crate) fn compute_hash(&self) -> EventHash {
         EventHash(Hash::from(serialise(&self.content).as_slice()))
     }
+
+    /// Getter for the event's creator.
+    pub fn creator(&self) -> &P {
+        &self.content.creator
+    }
+
+    pub(crate) fn parents(&self) -> impl Iterator<Item = &EventHash> {
+        self.content
+            .self_parent()
+            .into_iter()
+            .chain(self.content.other_parent())
+    }
 }
 
 #[cfg(all(feature = "mock", any(feature = "testing", test)))]
@@ -111,11 +123,6 @@ impl PackedEvent<Transaction, PeerId> {
         PackedEvent { content, signature }
     }
 
-    /// Getter for the event's creator.
-    pub fn creator(&self) -> &PeerId {
-        &self.content.creator
-    }
-
     /// Getter for the event's self-parent.
     pub fn self_parent(&self) -> Option<&EventHash> {
         self.content.self_parent()
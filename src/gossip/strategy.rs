@@ -0,0 +1,31 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+/// Policy used by [next_gossip_recipient](struct.Parsec.html#method.next_gossip_recipient) to
+/// choose the peer to send the next gossip request to, among the ones returned by
+/// [gossip_recipients](struct.Parsec.html#method.gossip_recipients).
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum GossipStrategy {
+    /// Picks a recipient uniformly at random, using the rng passed to `next_gossip_recipient`. This
+    /// is the default.
+    Random,
+    /// Picks the recipients in turn, in the order they were added to the section.
+    RoundRobin,
+    /// Picks the recipient whose gossip we received the longest time ago, or which never gossiped
+    /// to us.
+    LeastRecentlySynced,
+    /// Picks the recipient missing the most of the events we know of, as estimated from the last
+    /// event of its we know of.
+    MostMissingEvents,
+}
+
+impl Default for GossipStrategy {
+    fn default() -> Self {
+        GossipStrategy::Random
+    }
+}
@@ -42,7 +42,6 @@ impl Hash {
         Hash(bytes)
     }
 
-    #[cfg(feature = "mock")]
     pub fn as_bytes(&self) -> &[u8; HASH_LEN] {
         &self.0
     }
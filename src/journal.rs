@@ -0,0 +1,61 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    gossip::{FetchResponse, Request, Response},
+    id::PublicId,
+    network_event::NetworkEvent,
+    observation::{ConsensusMode, Observation},
+};
+
+/// A single input to a [Parsec](struct.Parsec.html) instance, as recorded in a
+/// [Journal](trait.Journal.html).
+#[serde(bound = "")]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum JournalEntry<T: NetworkEvent, P: PublicId> {
+    /// Call to `vote_for` with the given observation.
+    Vote(Observation<T, P>),
+    /// Call to `create_gossip` with the given recipient.
+    CreateGossip(P),
+    /// Call to `handle_request` with the given sender and request.
+    Request(P, Request<T, P>),
+    /// Call to `handle_response` with the given sender and response.
+    Response(P, Response<T, P>),
+    /// Call to `vote_for_with_mode` with the given observation and consensus mode.
+    VoteWithMode(Observation<T, P>, ConsensusMode),
+    /// Call to `vote_for_with_ttl` with the given observation and time-to-live.
+    VoteWithTtl(Observation<T, P>, usize),
+    /// Call to `handle_fetched` with the given sender and response.
+    Fetched(P, FetchResponse<T, P>),
+    /// Call to `prune`.
+    Prune,
+    /// Call to `create_checkpoint`.
+    CreateCheckpoint,
+}
+
+/// Append-only log of the inputs to a [Parsec](struct.Parsec.html) instance, registered using
+/// [set_journal](struct.Parsec.html#method.set_journal).
+///
+/// Every input is appended before it is handled, so the journal always contains everything the
+/// owning peer might have already sent to other peers. Handling the same inputs again in the same
+/// order is deterministic, so an instance created with the same arguments and settings as the
+/// original one and fed the journalled entries using
+/// [replay](struct.Parsec.html#method.replay) ends up in the same state as the original one.
+///
+/// The settings, i.e. the calls to `set_joint_consensus` and `set_common_coin`, are not
+/// journalled and have to be applied again before replaying. Neither are the calls to
+/// `switch_to_rekeyed_id`, which take the secret ID of the owning peer: a journal can only be
+/// replayed up to the first key rotation, so it should be restarted after each one, e.g. right
+/// after taking a [snapshot](struct.Parsec.html#method.snapshot).
+///
+/// Implementations are responsible for making the entries durable, e.g. by serialising them to a
+/// file and flushing it.
+pub trait Journal<T: NetworkEvent, P: PublicId> {
+    /// Appends `entry` to the journal.
+    fn append(&mut self, entry: &JournalEntry<T, P>);
+}
@@ -0,0 +1,263 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    block::Block,
+    error::{Error, Result},
+    id::PublicId,
+    network_event::NetworkEvent,
+    serialise,
+};
+use maidsafe_utilities::serialisation;
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    marker::PhantomData,
+    path::Path,
+};
+
+/// Appends blocks to a ledger, typically every block returned by
+/// [poll](struct.Parsec.html#method.poll), so they can be read back later using a
+/// [LedgerReader](struct.LedgerReader.html).
+///
+/// Each block is written with all its proofs as a record made of the length of the serialised
+/// block as a little-endian `u64` followed by the serialised block.
+pub struct LedgerWriter<W: Write> {
+    writer: W,
+}
+
+impl LedgerWriter<File> {
+    /// Opens the ledger file at `path` for appending, creating it if it doesn't exist yet.
+    pub fn open<Q: AsRef<Path>>(path: Q) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(file))
+    }
+}
+
+impl<W: Write> LedgerWriter<W> {
+    /// Creates a writer appending the ledger records to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Appends `block` to the ledger and flushes the underlying writer.
+    pub fn append<T: NetworkEvent, P: PublicId>(&mut self, block: &Block<T, P>) -> Result<()> {
+        let record = serialise(block);
+        self.writer
+            .write_all(&(record.len() as u64).to_le_bytes())?;
+        self.writer.write_all(&record)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Iterates over the blocks of a ledger written by a [LedgerWriter](struct.LedgerWriter.html),
+/// in the order they were appended.
+///
+/// The proofs of every block are verified again while reading, and every block must be chained to
+/// the one preceding it in the ledger, so a ledger which was corrupted, reordered or tampered with
+/// since it was written yields an error instead of the affected block. The iteration stops after
+/// the first error, as the position of the following records is unknown then.
+pub struct LedgerReader<R: Read, T: NetworkEvent, P: PublicId> {
+    reader: R,
+    failed: bool,
+    previous: Option<Block<T, P>>,
+    _phantom: PhantomData<(T, P)>,
+}
+
+impl<T: NetworkEvent, P: PublicId> LedgerReader<File, T, P> {
+    /// Opens the ledger file at `path` for reading.
+    pub fn open<Q: AsRef<Path>>(path: Q) -> Result<Self> {
+        Ok(Self::new(File::open(path)?))
+    }
+}
+
+impl<R: Read, T: NetworkEvent, P: PublicId> LedgerReader<R, T, P> {
+    /// Creates a reader iterating over the ledger records read from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            failed: false,
+            previous: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    // Reads the next record. Returns `Ok(None)` if the ledger ends cleanly before it.
+    fn read_record(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut length = [0; 8];
+        let mut read = 0;
+        while read < length.len() {
+            match self.reader.read(&mut length[read..]) {
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(Error::InvalidLedger),
+                Ok(count) => read += count,
+                Err(ref error) if error.kind() == io::ErrorKind::Interrupted => (),
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        let length = u64::from_le_bytes(length);
+        let mut record = Vec::new();
+        let _ = self.reader.by_ref().take(length).read_to_end(&mut record)?;
+        if record.len() as u64 != length {
+            return Err(Error::InvalidLedger);
+        }
+        Ok(Some(record))
+    }
+
+    fn read_block(&mut self) -> Result<Option<Block<T, P>>> {
+        let record = if let Some(record) = self.read_record()? {
+            record
+        } else {
+            return Ok(None);
+        };
+        let block: Block<T, P> =
+            serialisation::deserialise(&record).map_err(|_| Error::InvalidLedger)?;
+
+        if block.proofs().is_empty() {
+            return Err(Error::MissingVotes);
+        }
+        if !block
+            .proofs()
+            .iter()
+            .all(|proof| block.is_valid_proof(proof))
+        {
+            return Err(Error::SignatureFailure);
+        }
+        if let Some(ref previous) = self.previous {
+            if !block.follows(previous) {
+                return Err(Error::BrokenChain);
+            }
+        }
+        self.previous = Some(block.clone());
+        Ok(Some(block))
+    }
+}
+
+impl<R: Read, T: NetworkEvent, P: PublicId> Iterator for LedgerReader<R, T, P> {
+    type Item = Result<Block<T, P>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let result = self.read_block();
+        self.failed = result.is_err();
+        result.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock::{self, PeerId, Transaction},
+        observation::Observation,
+        vote::Vote,
+    };
+    use std::collections::BTreeMap;
+
+    fn create_block(
+        ids: &[PeerId],
+        payload: &str,
+        previous: Option<&Block<Transaction, PeerId>>,
+    ) -> Block<Transaction, PeerId> {
+        let payload = Observation::OpaquePayload(Transaction::new(payload));
+        let votes: BTreeMap<_, _> = ids
+            .iter()
+            .map(|id| (id.clone(), Vote::new(id, payload.clone())))
+            .collect();
+        let block = unwrap!(Block::new(&votes));
+        if let Some(previous) = previous {
+            block.in_chain(previous.sequence_number() + 1, Some(previous.hash()))
+        } else {
+            block
+        }
+    }
+
+    #[test]
+    fn write_and_read_back() {
+        let ids = mock::create_ids(3);
+        let one = create_block(&ids, "one", None);
+        let two = create_block(&ids[..2], "two", Some(&one));
+        let blocks = vec![one, two];
+
+        let mut writer = LedgerWriter::new(Vec::new());
+        for block in &blocks {
+            unwrap!(writer.append(block));
+        }
+        let ledger = writer.into_inner();
+
+        let read: Result<Vec<_>> = LedgerReader::new(ledger.as_slice()).collect();
+        assert_eq!(unwrap!(read), blocks);
+        assert!(LedgerReader::<_, Transaction, PeerId>::new(&[][..])
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn detect_truncated_ledger() {
+        let ids = mock::create_ids(3);
+        let one = create_block(&ids, "one", None);
+        let mut writer = LedgerWriter::new(Vec::new());
+        unwrap!(writer.append(&one));
+        unwrap!(writer.append(&create_block(&ids, "two", Some(&one))));
+        let mut ledger = writer.into_inner();
+        let _ = ledger.pop();
+
+        let mut reader = LedgerReader::<_, Transaction, PeerId>::new(ledger.as_slice());
+        assert!(unwrap!(reader.next()).is_ok());
+        assert_eq!(reader.next(), Some(Err(Error::InvalidLedger)));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn detect_tampered_payload() {
+        let ids = mock::create_ids(3);
+        let mut writer = LedgerWriter::new(Vec::new());
+        unwrap!(writer.append(&create_block(&ids, "pay Alice", None)));
+        let mut ledger = writer.into_inner();
+
+        // Replace the payload with one of the same length, so the record is still well-formed.
+        let position = unwrap!(ledger
+            .windows("Alice".len())
+            .position(|window| window == b"Alice"));
+        ledger[position..position + "Alice".len()].copy_from_slice(b"Mallo");
+
+        let mut reader = LedgerReader::<_, Transaction, PeerId>::new(ledger.as_slice());
+        assert_eq!(reader.next(), Some(Err(Error::SignatureFailure)));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn detect_reordered_ledger() {
+        let ids = mock::create_ids(3);
+        let one = create_block(&ids, "one", None);
+        let two = create_block(&ids, "two", Some(&one));
+        let three = create_block(&ids, "three", Some(&two));
+
+        // Each block is validly signed, but `three` isn't chained to `one`.
+        let mut writer = LedgerWriter::new(Vec::new());
+        unwrap!(writer.append(&one));
+        unwrap!(writer.append(&three));
+        unwrap!(writer.append(&two));
+        let ledger = writer.into_inner();
+
+        let mut reader = LedgerReader::<_, Transaction, PeerId>::new(ledger.as_slice());
+        assert_eq!(reader.next(), Some(Ok(one)));
+        assert_eq!(reader.next(), Some(Err(Error::BrokenChain)));
+        assert!(reader.next().is_none());
+    }
+}
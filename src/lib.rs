@@ -91,7 +91,9 @@
 //! * Calling [`Parsec::from_genesis`](struct.Parsec.html#method.from_genesis) if the peer is a
 //! member of the initial section, or
 //! [`Parsec::from_existing`](struct.Parsec.html#method.from_existing) if the peer joins an
-//! existing section, to construct a `Parsec` instance.
+//! existing section, to construct a `Parsec` instance. A joining peer can instead use
+//! [`Parsec::from_checkpoint`](struct.Parsec.html#method.from_checkpoint) to start from a
+//! checkpoint signed by the section rather than from its whole gossip history.
 //! * Calling [`Parsec::vote_for`](struct.Parsec.html#method.vote_for) whenever the peer is
 //! supposed to vote for a transaction (be it an application-specific, opaque payload, or a section
 //! mutation: a peer joining or being removed).
@@ -178,11 +180,16 @@ extern crate unwrap;
 pub mod dev_utils;
 
 mod block;
+mod checkpoint;
+mod common_coin;
+mod consensus_listener;
 mod dump_graph;
 mod error;
 mod gossip;
 mod hash;
 mod id;
+mod journal;
+mod ledger;
 mod meta_voting;
 mod network_event;
 mod observation;
@@ -190,6 +197,7 @@ mod parsec;
 mod parsec_helpers;
 mod peer_list;
 mod round_hash;
+mod stats;
 mod vote;
 
 #[cfg(all(test, feature = "mock"))]
@@ -207,13 +215,22 @@ pub mod mock;
 #[cfg(feature = "dump-graphs")]
 pub use crate::dump_graph::{DumpGraphMode, DIR, DUMP_MODE};
 pub use crate::{
-    block::Block,
+    block::{verify_block, Block, BlockCertificate, BlockHash, ElectionInfo},
+    checkpoint::Checkpoint,
+    common_coin::{CoinContext, CommonCoin, HashCoin, ThresholdCoin, ThresholdSigner},
+    consensus_listener::ConsensusListener,
     error::{Error, Result},
-    gossip::{EventHash, PackedEvent, Request, Response},
+    gossip::{
+        EventHash, Fetch, FetchResponse, GossipStrategy, PackedEvent, Request, Response,
+        MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+    },
     id::{Proof, PublicId, SecretId},
+    journal::{Journal, JournalEntry},
+    ledger::{LedgerReader, LedgerWriter},
     network_event::NetworkEvent,
-    observation::{ConsensusMode, Malice, Observation},
+    observation::{ConsensusMode, Malice, Observation, MAX_WEIGHT},
     parsec::Parsec,
+    stats::{MetaVoteProgress, Stats},
     vote::Vote,
 };
 
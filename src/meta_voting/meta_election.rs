@@ -8,9 +8,10 @@
 
 use super::{
     meta_event::{MetaEvent, MetaEventBuilder},
-    meta_vote::MetaVote,
+    meta_vote::{MetaVote, Step},
 };
 use crate::{
+    block::BlockHash,
     gossip::{EventIndex, Graph},
     id::PublicId,
     observation::{ObservationHash, ObservationKey},
@@ -24,7 +25,7 @@ lazy_static! {
     static ref EMPTY_BTREESET_EVENT_INDEX: BTreeSet<EventIndex> = BTreeSet::new();
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub(crate) struct UnconsensusedEvents {
     // Set of all events that carry a payload that hasn't yet been consensused.
     pub(crate) ordered_indices: BTreeSet<EventIndex>,
@@ -32,7 +33,7 @@ pub(crate) struct UnconsensusedEvents {
     pub(crate) indices_by_key: FnvHashMap<ObservationKey, BTreeSet<EventIndex>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub(crate) struct MetaElection {
     // Set of meta-events corresponding to the events in the gossip graph.
     pub(crate) meta_events: FnvHashMap<EventIndex, MetaEvent>,
@@ -49,11 +50,20 @@ pub(crate) struct MetaElection {
     pub(crate) unconsensused_events: UnconsensusedEvents,
     // Keys of the consensused blocks' payloads in the order they were consensused.
     pub(crate) consensus_history: Vec<ObservationKey>,
+    // Hash of the last block created for an entry of `consensus_history`.
+    pub(crate) last_block_hash: Option<BlockHash>,
+    // Number of meta-elections decided so far.
+    pub(crate) decided_elections: usize,
     // Topological index of the first unconsensused payload-carrying event or of the first observer
     // event, whichever is the greater.
     pub(crate) continue_consensus_start_index: usize,
     // Topological index of the first unconsensused payload-carrying event.
     pub(crate) new_consensus_start_index: usize,
+    // Whether a change of the voters is followed by a joint-consensus transition.
+    pub(crate) joint_consensus: bool,
+    // Voters of the previous meta-election with the voting weights they held in it, if the current
+    // one is a joint-consensus transition.
+    pub(crate) previous_voters: Option<PeerIndexMap<u64>>,
 }
 
 impl MetaElection {
@@ -65,8 +75,12 @@ impl MetaElection {
             interesting_events: PeerIndexMap::default(),
             unconsensused_events: UnconsensusedEvents::default(),
             consensus_history: Vec::new(),
+            last_block_hash: None,
+            decided_elections: 0,
             continue_consensus_start_index: 0,
             new_consensus_start_index: 0,
+            joint_consensus: false,
+            previous_voters: None,
         }
     }
 
@@ -128,10 +142,52 @@ impl MetaElection {
         &self.voters
     }
 
+    /// Voters of the previous meta-election with the voting weights they held in it, if the current
+    /// one is a joint-consensus transition, i.e. its decisions need a supermajority of both the
+    /// previous and the current voters.
+    pub fn previous_voters(&self) -> Option<&PeerIndexMap<u64>> {
+        self.previous_voters.as_ref()
+    }
+
+    pub fn set_joint_consensus(&mut self, joint_consensus: bool) {
+        self.joint_consensus = joint_consensus;
+    }
+
     pub fn consensus_history(&self) -> &[ObservationKey] {
         &self.consensus_history
     }
 
+    pub fn last_block_hash(&self) -> Option<&BlockHash> {
+        self.last_block_hash.as_ref()
+    }
+
+    pub fn decided_elections(&self) -> usize {
+        self.decided_elections
+    }
+
+    // Round and step of the most advanced meta-vote about each voter in the current
+    // meta-election.
+    pub fn meta_vote_progress(&self) -> PeerIndexMap<(usize, Step)> {
+        let mut progress = PeerIndexMap::new();
+        for (peer_index, meta_votes) in self
+            .meta_events
+            .values()
+            .flat_map(|meta_event| &meta_event.meta_votes)
+        {
+            let round_and_step = if let Some(meta_vote) = meta_votes.last() {
+                meta_vote.round_and_step()
+            } else {
+                continue;
+            };
+
+            let entry = progress.entry(peer_index).or_insert(round_and_step);
+            if round_and_step > *entry {
+                *entry = round_and_step;
+            }
+        }
+        progress
+    }
+
     pub fn interesting_events(&self) -> impl Iterator<Item = (PeerIndex, &[EventIndex])> {
         self.interesting_events
             .iter()
@@ -160,24 +216,54 @@ impl MetaElection {
         self.continue_consensus_start_index
     }
 
-    /// Starts new election.
+    pub fn new_consensus_start_index(&self) -> usize {
+        self.new_consensus_start_index
+    }
+
+    /// Discards meta-events of the events whose topological index is less than `end_index`.
+    pub fn prune(&mut self, end_index: usize) {
+        self.meta_events
+            .retain(|event_index, _| event_index.topological_index() >= end_index);
+    }
+
+    /// Starts new election. `voter_weights` are the weights the voters held in the decided
+    /// election, before `peer_list_changes`. `last_block_hash` is the hash of the last block
+    /// created for `decided_keys`, if any. Returns the keys of the payloads whose unconsensused
+    /// votes expired with it.
     pub fn new_election<P: PublicId>(
         &mut self,
         graph: &Graph<P>,
         decided_keys: Vec<ObservationKey>,
         peer_list_changes: Vec<PeerListChange>,
-    ) {
+        voter_weights: PeerIndexMap<u64>,
+        last_block_hash: Option<BlockHash>,
+    ) -> Vec<ObservationKey> {
         let peer_list_changed = !peer_list_changes.is_empty();
 
         self.update_voters(peer_list_changes);
+        self.previous_voters = if self.joint_consensus && peer_list_changed {
+            Some(voter_weights)
+        } else {
+            None
+        };
         self.update_unconsensused_events(&decided_keys);
-        self.update_new_consensus_start_index(graph.len());
-        self.update_continue_consensus_start_index(peer_list_changed);
-        self.update_meta_events(&decided_keys, peer_list_changed);
+        let expired_keys =
+            self.remove_expired_events(graph, self.consensus_history.len() + decided_keys.len());
+        // The meta-events of the descendants of the expired votes have to be computed again
+        // without them, the same as when the voters change.
+        let restart = peer_list_changed || !expired_keys.is_empty();
+        self.update_new_consensus_start_index(graph.end_index());
+        self.update_continue_consensus_start_index(restart);
+        self.update_meta_events(&decided_keys, restart);
         self.update_interesting_content(graph);
 
         self.round_hashes.clear();
+        if last_block_hash.is_some() {
+            self.last_block_hash = last_block_hash;
+        }
         self.consensus_history.extend(decided_keys);
+        self.decided_elections += 1;
+        expired_keys
     }
 
     pub fn initialise_round_hashes<'a, I, P>(&mut self, peer_ids: I)
@@ -262,6 +348,44 @@ impl MetaElection {
         }
     }
 
+    // Removes the unconsensused events carrying votes which expire once `history_len` payloads
+    // have been consensused. Returns the keys of their payloads.
+    fn remove_expired_events<P: PublicId>(
+        &mut self,
+        graph: &Graph<P>,
+        history_len: usize,
+    ) -> Vec<ObservationKey> {
+        let is_expired = |event_index: &EventIndex| {
+            graph
+                .get(*event_index)
+                .and_then(|event| event.vote_expiry())
+                .map_or(false, |expiry| expiry <= history_len)
+        };
+
+        let mut expired_keys = Vec::new();
+        for (key, indices) in &mut self.unconsensused_events.indices_by_key {
+            let expired: Vec<_> = indices.iter().cloned().filter(is_expired).collect();
+            if expired.is_empty() {
+                continue;
+            }
+
+            for event_index in expired {
+                let _ = indices.remove(&event_index);
+                let _ = self
+                    .unconsensused_events
+                    .ordered_indices
+                    .remove(&event_index);
+            }
+            expired_keys.push(*key);
+        }
+
+        self.unconsensused_events
+            .indices_by_key
+            .retain(|_, indices| !indices.is_empty());
+        expired_keys.sort();
+        expired_keys
+    }
+
     fn update_voters(&mut self, peer_list_changes: Vec<PeerListChange>) {
         for peer_list_change in peer_list_changes {
             match peer_list_change {
@@ -275,22 +399,27 @@ impl MetaElection {
                         log_or_panic!("Meta election doesn't contain {:?}", peer_index);
                     }
                 }
+                PeerListChange::Reweight(peer_index) => {
+                    if !self.voters.contains(peer_index) {
+                        log_or_panic!("Meta election doesn't contain {:?}", peer_index);
+                    }
+                }
             }
         }
     }
 
-    fn update_new_consensus_start_index(&mut self, graph_len: usize) {
+    fn update_new_consensus_start_index(&mut self, graph_end_index: usize) {
         self.new_consensus_start_index = self
             .unconsensused_events
             .ordered_indices
             .iter()
             .next()
             .map(|event_index| event_index.topological_index())
-            .unwrap_or(graph_len);
+            .unwrap_or(graph_end_index);
     }
 
-    fn update_continue_consensus_start_index(&mut self, peer_list_changed: bool) {
-        self.continue_consensus_start_index = if peer_list_changed {
+    fn update_continue_consensus_start_index(&mut self, restart: bool) {
+        self.continue_consensus_start_index = if restart {
             self.new_consensus_start_index
         } else {
             cmp::max(
@@ -305,8 +434,8 @@ impl MetaElection {
         };
     }
 
-    fn update_meta_events(&mut self, decided_keys: &[ObservationKey], peer_list_changed: bool) {
-        if peer_list_changed {
+    fn update_meta_events(&mut self, decided_keys: &[ObservationKey], restart: bool) {
+        if restart {
             self.meta_events.clear();
         } else {
             let new_consensus_start_index = self.new_consensus_start_index;
@@ -6,7 +6,10 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{bool_set::BoolSet, meta_vote_counts::MetaVoteCounts};
+use super::{
+    bool_set::BoolSet,
+    meta_vote_counts::{MetaVoteCounts, VoteWeights, VoterWeight},
+};
 use std::{
     collections::BTreeMap,
     fmt::{self, Debug, Formatter},
@@ -27,18 +30,18 @@ impl Estimates {
         if self.0.is_empty() {
             if let Some(toss) = coin_toss {
                 if toss {
-                    counts.estimates_true += 1;
+                    counts.estimates_true += counts.own_weight;
                 } else {
-                    counts.estimates_false += 1;
+                    counts.estimates_false += counts.own_weight;
                 }
                 self.0 = BoolSet::from_bool(toss);
             }
         } else {
             if counts.at_least_one_third(counts.estimates_true) && self.0.insert(true) {
-                counts.estimates_true += 1;
+                counts.estimates_true += counts.own_weight;
             }
             if counts.at_least_one_third(counts.estimates_false) && self.0.insert(false) {
-                counts.estimates_false += 1;
+                counts.estimates_false += counts.own_weight;
             }
         }
     }
@@ -58,10 +61,10 @@ impl Debug for BinValues {
 impl BinValues {
     fn calculate(&mut self, counts: &mut MetaVoteCounts) {
         if counts.is_supermajority(counts.estimates_true) && self.0.insert(true) {
-            counts.bin_values_true += 1;
+            counts.bin_values_true += counts.own_weight;
         }
         if counts.is_supermajority(counts.estimates_false) && self.0.insert(false) {
-            counts.bin_values_false += 1;
+            counts.bin_values_false += counts.own_weight;
         }
     }
 }
@@ -89,14 +92,14 @@ impl AuxValue {
             if bin_values_now.0.len() == 1 {
                 if bin_values_now.0.contains(true) {
                     self.0 = Some(true);
-                    counts.aux_values_true += 1;
+                    counts.aux_values_true += counts.own_weight;
                 } else {
                     self.0 = Some(false);
-                    counts.aux_values_false += 1;
+                    counts.aux_values_false += counts.own_weight;
                 }
             } else if bin_values_now.0.len() == 2 {
                 self.0 = Some(true);
-                counts.aux_values_true += 1;
+                counts.aux_values_true += counts.own_weight;
             }
         }
     }
@@ -157,38 +160,38 @@ impl MetaVoteValues {
         values.estimates = Estimates::from_initial_value(value);
         MetaVoteValues::Undecided(values)
     }
-    pub(crate) fn count(self) -> MetaVoteCounts {
-        // Counts the contribution of these MetaVoteValues
+    pub(crate) fn count(self, weight: VoterWeight) -> MetaVoteCounts {
+        // Counts the contribution of these MetaVoteValues cast with the given weight
         let mut counts = MetaVoteCounts::default();
         match self {
             MetaVoteValues::Decided(value) => {
                 counts.decision = Some(value);
                 if value {
-                    counts.estimates_true = 1;
-                    counts.bin_values_true = 1;
-                    counts.aux_values_true = 1;
+                    counts.estimates_true = weight;
+                    counts.bin_values_true = weight;
+                    counts.aux_values_true = weight;
                 } else {
-                    counts.estimates_false = 1;
-                    counts.bin_values_false = 1;
-                    counts.aux_values_false = 1;
+                    counts.estimates_false = weight;
+                    counts.bin_values_false = weight;
+                    counts.aux_values_false = weight;
                 }
             }
             MetaVoteValues::Undecided(values) => {
                 if values.estimates.0.contains(true) {
-                    counts.estimates_true = 1;
+                    counts.estimates_true = weight;
                 }
                 if values.estimates.0.contains(false) {
-                    counts.estimates_false = 1;
+                    counts.estimates_false = weight;
                 }
                 if values.bin_values.0.contains(true) {
-                    counts.bin_values_true = 1;
+                    counts.bin_values_true = weight;
                 }
                 if values.bin_values.0.contains(false) {
-                    counts.bin_values_false = 1;
+                    counts.bin_values_false = weight;
                 }
                 match values.aux_value.0 {
-                    Some(true) => counts.aux_values_true = 1,
-                    Some(false) => counts.aux_values_false = 1,
+                    Some(true) => counts.aux_values_true = weight,
+                    Some(false) => counts.aux_values_false = weight,
                     None => (),
                 }
             }
@@ -421,30 +424,34 @@ impl MetaVote {
 
     pub fn new_for_observer(
         initial_estimate: bool,
-        others: &[&[MetaVote]],
-        total_peers: usize,
+        others: &[(&[MetaVote], VoterWeight)],
+        weights: VoteWeights,
     ) -> Vec<Self> {
         let initial = Self {
             values: MetaVoteValues::from_initial_estimate(initial_estimate),
             ..Default::default()
         };
-        Self::next_votes(&[initial], others, &BTreeMap::new(), total_peers)
+        Self::next_votes(&[initial], others, &BTreeMap::new(), weights)
     }
 
     /// Create temporary next meta-votes. They must be finalized by calling `next_final` before
     /// passing them to `MetaEvent`.
-    pub fn next_temp(parent: &[MetaVote], others: &[&[MetaVote]], total_peers: usize) -> Vec<Self> {
-        Self::next_votes(parent, others, &BTreeMap::new(), total_peers)
+    pub fn next_temp(
+        parent: &[MetaVote],
+        others: &[(&[MetaVote], VoterWeight)],
+        weights: VoteWeights,
+    ) -> Vec<Self> {
+        Self::next_votes(parent, others, &BTreeMap::new(), weights)
     }
 
     /// Finalize temporary meta-votes.
     pub fn next_final(
         temp: &[MetaVote],
-        others: &[&[MetaVote]],
+        others: &[(&[MetaVote], VoterWeight)],
         coin_tosses: &BTreeMap<usize, bool>,
-        total_peers: usize,
+        weights: VoteWeights,
     ) -> Vec<Self> {
-        Self::next_votes(temp, others, coin_tosses, total_peers)
+        Self::next_votes(temp, others, coin_tosses, weights)
     }
 
     pub fn contains_aux_value(&self) -> bool {
@@ -470,13 +477,13 @@ impl MetaVote {
 
     fn next_votes(
         prev: &[MetaVote],
-        others: &[&[MetaVote]],
+        others: &[(&[MetaVote], VoterWeight)],
         coin_tosses: &BTreeMap<usize, bool>,
-        total_peers: usize,
+        weights: VoteWeights,
     ) -> Vec<Self> {
         let mut next = Vec::new();
         for vote in prev {
-            let counts = MetaVoteCounts::new(vote, others, total_peers);
+            let counts = MetaVoteCounts::new(vote, others, weights);
             let mut updated = *vote;
             updated.update(counts, &coin_tosses);
             let decided = vote.is_decided();
@@ -486,8 +493,7 @@ impl MetaVote {
             }
         }
 
-        while let Some(next_meta_vote) =
-            Self::next_vote(next.last(), others, &coin_tosses, total_peers)
+        while let Some(next_meta_vote) = Self::next_vote(next.last(), others, &coin_tosses, weights)
         {
             next.push(next_meta_vote);
         }
@@ -514,20 +520,20 @@ impl MetaVote {
 
     fn next_vote(
         parent: Option<&Self>,
-        others: &[&[MetaVote]],
+        others: &[(&[MetaVote], VoterWeight)],
         coin_tosses: &BTreeMap<usize, bool>,
-        total_peers: usize,
+        weights: VoteWeights,
     ) -> Option<MetaVote> {
         let parent = parent?;
 
         if parent.is_decided() {
             return None;
         }
-        let counts = MetaVoteCounts::new(parent, others, total_peers);
+        let counts = MetaVoteCounts::new(parent, others, weights);
         if counts.is_supermajority(counts.aux_values_set()) {
             let coin_toss = coin_tosses.get(&parent.round);
             let mut next = parent.increase_step(&counts, coin_toss.cloned());
-            let new_counts = MetaVoteCounts::new(&next, others, total_peers);
+            let new_counts = MetaVoteCounts::new(&next, others, weights);
             next.update(new_counts, &coin_tosses);
             Some(next)
         } else {
@@ -561,6 +567,17 @@ pub(crate) enum Step {
     GenuineFlip,
 }
 
+impl Step {
+    // The number of the step as used in the PARSEC whitepaper.
+    pub fn index(self) -> usize {
+        match self {
+            Step::ForcedTrue => 0,
+            Step::ForcedFalse => 1,
+            Step::GenuineFlip => 2,
+        }
+    }
+}
+
 impl Default for Step {
     fn default() -> Step {
         Step::ForcedTrue
@@ -569,11 +586,6 @@ impl Default for Step {
 
 impl Debug for Step {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        let step = match self {
-            Step::ForcedTrue => 0,
-            Step::ForcedFalse => 1,
-            Step::GenuineFlip => 2,
-        };
-        write!(f, "{}", step)
+        write!(f, "{}", self.index())
     }
 }
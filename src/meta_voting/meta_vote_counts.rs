@@ -7,21 +7,63 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::meta_vote::MetaVote;
-use crate::observation::is_more_than_two_thirds;
-use std::ops::AddAssign;
+use crate::observation::is_more_than_two_thirds_of_weight;
+use std::ops::{Add, AddAssign};
+
+// Voting weight of a voter, or the sum of those of several voters. During a joint-consensus
+// transition, the weight among the previous voters is counted apart.
+#[derive(Clone, Copy, Default, Debug)]
+pub(crate) struct VoterWeight {
+    // Weight among the voters of the meta-election.
+    pub current: u128,
+    // Weight among the previous voters, or zero if the voter isn't one of them or the
+    // meta-election isn't a joint-consensus transition.
+    pub previous: u128,
+}
+
+impl Add for VoterWeight {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        VoterWeight {
+            current: self.current + other.current,
+            previous: self.previous + other.previous,
+        }
+    }
+}
+
+impl AddAssign for VoterWeight {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+// Voting weights used to count the meta votes of a meta-election.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct VoteWeights {
+    // Weight of the creator of the event the meta votes are computed for.
+    pub own: VoterWeight,
+    // Total weight of the voters.
+    pub total: u128,
+    // Total weight of the previous voters, if the meta-election is a joint-consensus transition.
+    pub previous_total: Option<u128>,
+}
 
 // This is used to collect the meta votes of other events relating to a single (binary) meta vote at
-// a given round and step.
+// a given round and step. Each meta vote counts with the voting weight of the creator of the event
+// carrying it.
 #[derive(Default, Debug)]
 pub(crate) struct MetaVoteCounts {
-    pub estimates_true: usize,
-    pub estimates_false: usize,
-    pub bin_values_true: usize,
-    pub bin_values_false: usize,
-    pub aux_values_true: usize,
-    pub aux_values_false: usize,
+    pub estimates_true: VoterWeight,
+    pub estimates_false: VoterWeight,
+    pub bin_values_true: VoterWeight,
+    pub bin_values_false: VoterWeight,
+    pub aux_values_true: VoterWeight,
+    pub aux_values_false: VoterWeight,
     pub decision: Option<bool>,
-    pub total_peers: usize,
+    pub own_weight: VoterWeight,
+    pub total_weight: u128,
+    pub previous_total_weight: Option<u128>,
 }
 
 impl AddAssign for MetaVoteCounts {
@@ -39,48 +81,69 @@ impl AddAssign for MetaVoteCounts {
 impl MetaVoteCounts {
     // Construct a `MetaVoteCounts` by collecting details from all meta votes which are for the
     // given `parent`'s `round` and `step`.  These results will include info from our own `parent`
-    // meta vote.
-    pub fn new(parent: &MetaVote, others: &[&[MetaVote]], total_peers: usize) -> Self {
+    // meta vote. `others` are paired with the weights of their voters.
+    pub fn new(
+        parent: &MetaVote,
+        others: &[(&[MetaVote], VoterWeight)],
+        weights: VoteWeights,
+    ) -> Self {
         let mut counts = MetaVoteCounts::default();
-        counts.total_peers = total_peers;
-        for vote in others
+        counts.own_weight = weights.own;
+        counts.total_weight = weights.total;
+        counts.previous_total_weight = weights.previous_total;
+        for (vote, weight) in others
             .iter()
-            .filter_map(|other| {
+            .filter_map(|(other, weight)| {
                 other
                     .iter()
                     .filter(|vote| vote.round_and_step() == parent.round_and_step())
                     .last()
+                    .map(|vote| (vote, *weight))
             })
-            .chain(Some(parent))
+            .chain(Some((parent, weights.own)))
         {
-            let contribution = vote.values.count();
+            let contribution = vote.values.count(weight);
             counts += contribution;
         }
         counts
     }
 
-    pub fn aux_values_set(&self) -> usize {
+    pub fn aux_values_set(&self) -> VoterWeight {
         self.aux_values_true + self.aux_values_false
     }
 
-    pub fn is_supermajority(&self, count: usize) -> bool {
-        is_more_than_two_thirds(count, self.total_peers)
+    // Returns whether `weight` is more than two thirds of the total weight of the voters and,
+    // during a joint-consensus transition, of the previous voters too.
+    pub fn is_supermajority(&self, weight: VoterWeight) -> bool {
+        is_more_than_two_thirds_of_weight(weight.current, self.total_weight)
+            && self
+                .previous_total_weight
+                .map_or(true, |previous_total_weight| {
+                    is_more_than_two_thirds_of_weight(weight.previous, previous_total_weight)
+                })
     }
 
-    pub fn at_least_one_third(&self, count: usize) -> bool {
-        3 * count >= self.total_peers
+    pub fn at_least_one_third(&self, weight: VoterWeight) -> bool {
+        3 * weight.current >= self.total_weight
     }
 
     pub fn check_exceeding(&self) {
-        let is_exceeding = self.estimates_true > self.total_peers
-            || self.estimates_false > self.total_peers
-            || self.bin_values_true > self.total_peers
-            || self.bin_values_false > self.total_peers
-            || self.aux_values_true > self.total_peers
-            || self.aux_values_false > self.total_peers;
+        let previous_total_weight = self.previous_total_weight.unwrap_or(0);
+        let is_exceeding = [
+            self.estimates_true,
+            self.estimates_false,
+            self.bin_values_true,
+            self.bin_values_false,
+            self.aux_values_true,
+            self.aux_values_false,
+        ]
+        .iter()
+        .any(|weight| {
+            weight.current > self.total_weight || weight.previous > previous_total_weight
+        });
 
         if is_exceeding {
-            log_or_panic!("Having count exceeding total peers {:?}", self);
+            log_or_panic!("Having count exceeding total weight {:?}", self);
         }
     }
 }
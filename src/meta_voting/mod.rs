@@ -22,4 +22,5 @@ pub(crate) use self::{
     meta_election::MetaElection,
     meta_event::{MetaEvent, MetaEventBuilder, Observer},
     meta_vote::{MetaVote, Step},
+    meta_vote_counts::{VoteWeights, VoterWeight},
 };
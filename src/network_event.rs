@@ -14,4 +14,14 @@ use std::fmt::Debug;
 pub trait NetworkEvent:
     Clone + Eq + Ord + PartialEq + PartialOrd + Serialize + DeserializeOwned + Debug
 {
+    /// Returns the priority of this payload over the other payloads consensused in the same
+    /// meta-election: the blocks with higher priorities come first. Payloads with the same priority
+    /// keep the order in which they were decided. The observations other than `OpaquePayload` come
+    /// before all the payloads, whatever their priority.
+    ///
+    /// The priority must only depend on the payload, so that all the peers order the blocks the
+    /// same way.
+    fn priority(&self) -> i32 {
+        0
+    }
 }
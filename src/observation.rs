@@ -27,13 +27,21 @@ use std::{
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Observation<T: NetworkEvent, P: PublicId> {
     /// Genesis group
-    Genesis(BTreeSet<P>),
-    /// Vote to add the indicated peer to the network.
+    Genesis {
+        /// Public ids of the peers present at the network startup
+        group: BTreeSet<P>,
+        /// Voting weights of the peers of `group` whose weight isn't 1
+        weights: BTreeMap<P, u64>,
+    },
+    /// Vote to add the indicated peer to the network. If the peer is already a member, the vote
+    /// only changes its voting weight.
     Add {
         /// Public id of the peer to be added
         peer_id: P,
         /// Extra arbitrary information for use by the client
         related_info: Vec<u8>,
+        /// Voting weight of the peer once added, between 1 and `MAX_WEIGHT`
+        weight: u64,
     },
     /// Vote to remove the indicated peer from the network.
     Remove {
@@ -51,9 +59,67 @@ pub enum Observation<T: NetworkEvent, P: PublicId> {
     },
     /// Vote for an event which is opaque to Parsec.
     OpaquePayload(T),
+    /// Vote to rotate the key of a peer. The peer keeps its place in the section, only the id it
+    /// signs with changes from `old` to `new`.
+    Rekey {
+        /// Public id the peer currently signs with
+        old: P,
+        /// Public id the peer is going to sign with
+        new: P,
+        /// Signature of the serialised `new` by `old`
+        proof: P::Signature,
+    },
+    /// Vote to add and remove several peers at once. The changes are applied together when the
+    /// block is consensused, so the section doesn't go through the intermediate sets of voters.
+    /// A peer listed in both `add` and `remove` is removed.
+    Reconfigure {
+        /// Public ids of the peers to be added, with their voting weights. As with `Add`, a peer
+        /// which is already a member only gets its voting weight changed.
+        add: BTreeMap<P, u64>,
+        /// Public ids of the peers to be removed
+        remove: BTreeSet<P>,
+        /// Extra arbitrary information for use by the client
+        related_info: Vec<u8>,
+    },
 }
 
 impl<T: NetworkEvent, P: PublicId> Observation<T, P> {
+    /// Creates an `Observation::Rekey` rotating the key of the peer owning `old_id` to `new`,
+    /// proven by signing `new` with `old_id`.
+    pub fn new_rekey<S: SecretId<PublicId = P>>(old_id: &S, new: P) -> Self {
+        let proof = old_id.sign_detached(&rekey_proof_data(old_id.public_id(), &new));
+        Observation::Rekey {
+            old: old_id.public_id().clone(),
+            new,
+            proof,
+        }
+    }
+
+    // Priority of this observation among the observations consensused in the same meta-election.
+    // The observations other than `OpaquePayload` change the section, so they come before all the
+    // opaque payloads, whatever their `NetworkEvent::priority`.
+    pub(crate) fn priority(&self) -> i64 {
+        if let Observation::OpaquePayload(ref payload) = *self {
+            i64::from(payload.priority())
+        } else {
+            i64::from(i32::MAX) + 1
+        }
+    }
+
+    // Returns whether all the voting weights this observation carries are between 1 and
+    // `MAX_WEIGHT`, and only concern peers of the genesis group for `Genesis`.
+    pub(crate) fn has_valid_weights(&self) -> bool {
+        let is_valid = |weight: &u64| *weight > 0 && *weight <= MAX_WEIGHT;
+        match self {
+            Observation::Genesis { group, weights } => weights
+                .iter()
+                .all(|(peer_id, weight)| group.contains(peer_id) && is_valid(weight)),
+            Observation::Add { weight, .. } => is_valid(weight),
+            Observation::Reconfigure { add, .. } => add.values().all(is_valid),
+            _ => true,
+        }
+    }
+
     pub(crate) fn is_opaque(&self) -> bool {
         if let Observation::OpaquePayload(_) = *self {
             true
@@ -66,9 +132,23 @@ impl<T: NetworkEvent, P: PublicId> Observation<T, P> {
 impl<T: NetworkEvent, P: PublicId> Debug for Observation<T, P> {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         match self {
-            Observation::Genesis(group) => write!(formatter, "Genesis({:?})", group),
+            Observation::Genesis { group, weights } if weights.is_empty() => {
+                write!(formatter, "Genesis({:?})", group)
+            }
+            Observation::Genesis { group, weights } => {
+                write!(formatter, "Genesis({:?}, weights: {:?})", group, weights)
+            }
             Observation::Add { peer_id, .. } => write!(formatter, "Add({:?})", peer_id),
             Observation::Remove { peer_id, .. } => write!(formatter, "Remove({:?})", peer_id),
+            Observation::Rekey { old, new, .. } => {
+                write!(formatter, "Rekey({:?} -> {:?})", old, new)
+            }
+            Observation::Reconfigure { add, remove, .. } => write!(
+                formatter,
+                "Reconfigure {{ add: {:?}, remove: {:?} }}",
+                add.keys().collect::<Vec<_>>(),
+                remove
+            ),
             Observation::Accusation { offender, malice } => {
                 write!(formatter, "Accusation {{ {:?}, {:?} }}", offender, malice)
             }
@@ -240,11 +320,14 @@ impl Debug for ObservationHash {
 }
 
 // Container for observation with its metadata.
-#[derive(Debug)]
+#[serde(bound = "")]
+#[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct ObservationInfo<T: NetworkEvent, P: PublicId> {
     pub(crate) observation: Observation<T, P>,
     pub(crate) consensused: bool,
     pub(crate) created_by_us: bool,
+    // Whether all the votes for this observation expired before it was consensused.
+    pub(crate) expired: bool,
 }
 
 impl<T: NetworkEvent, P: PublicId> ObservationInfo<T, P> {
@@ -253,6 +336,7 @@ impl<T: NetworkEvent, P: PublicId> ObservationInfo<T, P> {
             observation,
             consensused: false,
             created_by_us: false,
+            expired: false,
         }
     }
 }
@@ -264,10 +348,11 @@ pub(crate) type ObservationStore<T, P> = BTreeMap<ObservationKey, ObservationInf
 pub(crate) type ObservationForStore<T, P> = Option<(ObservationKey, ObservationInfo<T, P>)>;
 
 // Key to compare observations.
-#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
 pub(crate) enum ObservationKey {
     Single(ObservationHash, PeerIndex),
     Supermajority(ObservationHash),
+    Threshold(ObservationHash, u8),
 }
 
 impl ObservationKey {
@@ -275,6 +360,7 @@ impl ObservationKey {
         match consensus_mode {
             ConsensusMode::Single => ObservationKey::Single(hash, creator),
             ConsensusMode::Supermajority => ObservationKey::Supermajority(hash),
+            ConsensusMode::Threshold(percent) => ObservationKey::Threshold(hash, percent),
         }
     }
 
@@ -282,6 +368,7 @@ impl ObservationKey {
         match *self {
             ObservationKey::Single(ref hash, _) => hash,
             ObservationKey::Supermajority(ref hash) => hash,
+            ObservationKey::Threshold(ref hash, _) => hash,
         }
     }
 
@@ -290,7 +377,9 @@ impl ObservationKey {
             ObservationKey::Single(ref hash, creator) => {
                 other_hash == hash && other_creator == creator
             }
-            ObservationKey::Supermajority(ref hash) => other_hash == hash,
+            ObservationKey::Supermajority(ref hash) | ObservationKey::Threshold(ref hash, _) => {
+                other_hash == hash
+            }
         }
     }
 
@@ -298,13 +387,14 @@ impl ObservationKey {
         match *self {
             ObservationKey::Single(..) => ConsensusMode::Single,
             ObservationKey::Supermajority(..) => ConsensusMode::Supermajority,
+            ObservationKey::Threshold(_, percent) => ConsensusMode::Threshold(percent),
         }
     }
 
     pub fn peer_index(&self) -> Option<PeerIndex> {
         match *self {
             ObservationKey::Single(_, peer_index) => Some(peer_index),
-            ObservationKey::Supermajority(_) => None,
+            ObservationKey::Supermajority(_) | ObservationKey::Threshold(..) => None,
         }
     }
 
@@ -325,15 +415,28 @@ impl ObservationKey {
 }
 
 /// Number of votes necessary to reach consensus on an `OpaquePayload`.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum ConsensusMode {
     /// One vote is enough.
     Single,
     /// Supermajority (more than 2/3) is required.
     Supermajority,
+    /// More than the given percentage of the votes is required, e.g. `Threshold(50)` for a simple
+    /// majority. The votes are counted with the voting weights of the peers, like the
+    /// supermajority. Values of 100 and above are invalid, as no weight is more than all of it: use
+    /// `Threshold(99)` to require the votes of all the peers of a small section.
+    Threshold(u8),
 }
 
 impl ConsensusMode {
+    // Returns whether this mode can be reached, i.e. isn't a threshold of 100% or more.
+    pub(crate) fn is_valid(self) -> bool {
+        match self {
+            ConsensusMode::Threshold(percent) => percent < 100,
+            ConsensusMode::Single | ConsensusMode::Supermajority => true,
+        }
+    }
+
     pub(crate) fn of<T: NetworkEvent, P: PublicId>(self, observation: &Observation<T, P>) -> Self {
         if observation.is_opaque() {
             self
@@ -343,21 +446,53 @@ impl ConsensusMode {
     }
 }
 
+/// Largest voting weight a peer can be given, so that the total weight of any number of peers can
+/// be compared to the supermajority and threshold fractions without overflowing.
+pub const MAX_WEIGHT: u64 = u32::MAX as u64;
+
 /// Returns whether `small` is more than two thirds of `large`.
+#[cfg(any(test, feature = "testing"))]
 pub fn is_more_than_two_thirds(small: usize, large: usize) -> bool {
     3 * small > 2 * large
 }
 
-#[cfg(any(all(test, feature = "mock"), feature = "dump-graphs"))]
+// Returns whether `weight` is more than two thirds of `total_weight`.
+pub(crate) fn is_more_than_two_thirds_of_weight(weight: u128, total_weight: u128) -> bool {
+    3 * weight > 2 * total_weight
+}
+
+// Returns whether `weight` is more than `percent` percent of `total_weight`.
+pub(crate) fn is_more_than_percent_of_weight(
+    weight: u128,
+    percent: u8,
+    total_weight: u128,
+) -> bool {
+    100 * weight > u128::from(percent) * total_weight
+}
+
+// Tags the data signed by the proof of an `Observation::Rekey`, so that no other signature by the
+// old id can pass for one.
+const REKEY_PROOF_TAG: &[u8] = b"parsec-rekey-proof";
+
+fn rekey_proof_data<P: PublicId>(old: &P, new: &P) -> Vec<u8> {
+    serialise(&(REKEY_PROOF_TAG, old, new))
+}
+
+// Returns whether `proof` is the signature of `new` by `old`, as required by `Observation::Rekey`.
+pub(crate) fn is_valid_rekey_proof<P: PublicId>(old: &P, new: &P, proof: &P::Signature) -> bool {
+    old.verify_signature(proof, &rekey_proof_data(old, new))
+}
+
 pub(crate) mod snapshot {
     use super::*;
     use crate::{id::SecretId, peer_list::PeerList};
 
     #[serde(bound = "")]
-    #[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
+    #[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
     pub(crate) enum ObservationKeySnapshot<P: PublicId> {
         Supermajority(ObservationHash),
         Single(ObservationHash, P),
+        Threshold(ObservationHash, u8),
     }
 
     impl<P: PublicId> ObservationKeySnapshot<P> {
@@ -369,12 +504,32 @@ pub(crate) mod snapshot {
                 ObservationKey::Supermajority(hash) => {
                     Some(ObservationKeySnapshot::Supermajority(hash))
                 }
+                ObservationKey::Threshold(hash, percent) => {
+                    Some(ObservationKeySnapshot::Threshold(hash, percent))
+                }
                 ObservationKey::Single(hash, peer_index) => peer_list
                     .get(peer_index)
                     .map(|peer| peer.id().clone())
                     .map(|peer_id| ObservationKeySnapshot::Single(hash, peer_id)),
             }
         }
+
+        pub fn to_key<S>(&self, peer_list: &PeerList<S>) -> Option<ObservationKey>
+        where
+            S: SecretId<PublicId = P>,
+        {
+            match *self {
+                ObservationKeySnapshot::Supermajority(hash) => {
+                    Some(ObservationKey::Supermajority(hash))
+                }
+                ObservationKeySnapshot::Threshold(hash, percent) => {
+                    Some(ObservationKey::Threshold(hash, percent))
+                }
+                ObservationKeySnapshot::Single(hash, ref peer_id) => peer_list
+                    .get_index(peer_id)
+                    .map(|peer_index| ObservationKey::Single(hash, peer_index)),
+            }
+        }
     }
 }
 
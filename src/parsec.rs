@@ -8,40 +8,51 @@
 
 #[cfg(all(test, feature = "mock"))]
 use crate::dev_utils::ParsedContents;
-#[cfg(all(test, feature = "malice-detection", feature = "mock"))]
-use crate::gossip::EventHash;
 #[cfg(all(test, any(feature = "testing", feature = "mock")))]
 use crate::gossip::GraphSnapshot;
+#[cfg(any(feature = "testing", all(test, feature = "mock")))]
+use crate::mock::{PeerId, Transaction};
 #[cfg(feature = "malice-detection")]
 use crate::observation::Malice;
 use crate::{
-    block::{Block, BlockGroup},
+    block::{Block, BlockGroup, BlockHash, ElectionInfo},
+    checkpoint::{Checkpoint, CheckpointContent},
+    common_coin::{CoinContext, CommonCoin, HashCoin},
+    consensus_listener::ConsensusListener,
     dump_graph,
     error::{Error, Result},
     gossip::{
-        Event, EventContextRef, EventIndex, Graph, IndexedEventRef, PackedEvent, Request, Response,
+        is_supported_version, split_into_chunks, Event, EventContextRef, EventHash, EventIndex,
+        Fetch, FetchResponse, GossipStrategy, Graph, IndexedEventRef, PackedEvent, Request,
+        Response, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
     },
+    hash::Hash,
     id::{PublicId, SecretId},
-    meta_voting::{MetaElection, MetaEvent, MetaEventBuilder, MetaVote, Observer, Step},
+    journal::{Journal, JournalEntry},
+    meta_voting::{
+        MetaElection, MetaEvent, MetaEventBuilder, MetaVote, Observer, Step, VoteWeights,
+        VoterWeight,
+    },
     network_event::NetworkEvent,
     observation::{
-        is_more_than_two_thirds, ConsensusMode, Observation, ObservationHash, ObservationKey,
-        ObservationStore,
+        is_more_than_percent_of_weight, is_more_than_two_thirds_of_weight, is_valid_rekey_proof,
+        snapshot::ObservationKeySnapshot, ConsensusMode, Observation, ObservationHash,
+        ObservationInfo, ObservationKey, ObservationStore, MAX_WEIGHT,
     },
     parsec_helpers::find_interesting_content_for_event,
     peer_list::{Peer, PeerIndex, PeerIndexMap, PeerIndexSet, PeerList, PeerListChange, PeerState},
-};
-#[cfg(any(feature = "testing", all(test, feature = "mock")))]
-use crate::{
-    hash::Hash,
-    mock::{PeerId, Transaction},
+    stats::{Counters, MetaVoteProgress, Stats},
+    vote::Vote,
 };
 use fnv::FnvHashSet;
 use itertools::Itertools;
+use maidsafe_utilities::serialisation;
+use rand::Rng;
 #[cfg(any(test, feature = "testing"))]
 use std::ops::{Deref, DerefMut};
 use std::{
-    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    cmp,
+    collections::{btree_map::Entry, BTreeMap, BTreeSet, HashMap, VecDeque},
     iter,
     marker::PhantomData,
     mem, usize,
@@ -79,7 +90,8 @@ use std::{
 /// [vote_for](struct.Parsec.html#method.vote_for) method.
 ///
 /// The [poll](struct.Parsec.html#method.poll) method is used to get the observations in the
-/// consensused order.
+/// consensused order. Alternatively, they can be pushed to a
+/// [ConsensusListener](trait.ConsensusListener.html) as soon as they are consensused.
 ///
 /// Most public methods return an error if called after the owning peer has been removed from the
 /// section, i.e. a block with payload `Observation::Remove(our_id)` has been made stable.
@@ -94,6 +106,8 @@ pub struct Parsec<T: NetworkEvent, S: SecretId> {
     observations: ObservationStore<T, S::PublicId>,
     // Consensused network events that have not been returned via `poll()` yet.
     consensused_blocks: VecDeque<BlockGroup<T, S::PublicId>>,
+    // Receiver of the consensused blocks instead of `poll()`, if registered.
+    consensus_listener: Option<Box<dyn ConsensusListener<T, S::PublicId> + Send>>,
     // The map of meta votes of the events on each consensus block.
     meta_election: MetaElection,
     consensus_mode: ConsensusMode,
@@ -102,6 +116,31 @@ pub struct Parsec<T: NetworkEvent, S: SecretId> {
     pending_accusations: Accusations<T, S::PublicId>,
     // Events to be inserted into the gossip graph when this node becomes voter.
     pending_events: Vec<PendingEvent<T, S::PublicId>>,
+    // Log of the inputs to this instance, if registered.
+    journal: Option<Box<dyn Journal<T, S::PublicId> + Send>>,
+    // Maximum total serialised size of the events in a single gossip message, if limited.
+    max_gossip_size: Option<usize>,
+    // Gossip exchanges split over several messages, by the peer we exchange them with.
+    chunked_syncs: PeerIndexMap<ChunkedSync<T, S::PublicId>>,
+    // Newest gossip protocol version supported by each peer, as advertised in its last message.
+    peer_protocol_versions: PeerIndexMap<u32>,
+    // Events received before some of their parents, waiting for them to be inserted.
+    orphan_events: OrphanPool<T, S::PublicId>,
+    // Policy of `next_gossip_recipient`.
+    gossip_strategy: GossipStrategy,
+    // Last peer returned by `next_gossip_recipient`, if any.
+    last_gossip_recipient: Option<PeerIndex>,
+    // Counters of the activity of this instance, reported by `stats()`.
+    counters: Counters,
+    // Source of the coin tossed by the meta-elections.
+    common_coin: Box<dyn CommonCoin<S::PublicId> + Send>,
+    // Round hashes of the coins we already published our share of in the current meta-election.
+    published_coin_shares: BTreeSet<Hash>,
+    // Our last event preceding the checkpoint we created for each peer expected to join from it,
+    // so we only gossip the events following it to them.
+    checkpoint_frontiers: PeerIndexMap<EventIndex>,
+    // Peers we reached consensus on adding to the section after it started.
+    added_peers: PeerIndexSet,
     // True to disable processing consensus on this instance to speed up processing for irrelevant
     // parsec instances.
     #[cfg(any(test, feature = "testing"))]
@@ -121,10 +160,43 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         genesis_group: &BTreeSet<S::PublicId>,
         consensus_mode: ConsensusMode,
     ) -> Self {
+        let genesis_weights = genesis_group
+            .iter()
+            .map(|peer_id| (peer_id.clone(), 1))
+            .collect();
+        Self::from_weighted_genesis(our_id, &genesis_weights, consensus_mode)
+    }
+
+    /// Same as [from_genesis](struct.Parsec.html#method.from_genesis), but the genesis peer IDs
+    /// are paired with their voting weights, which must be between 1 and
+    /// [MAX_WEIGHT](constant.MAX_WEIGHT.html). A supermajority then means more than two thirds of
+    /// the total weight of the voters rather than of their number.
+    ///
+    /// The weights are carried by the genesis observation, so the peers joining the section later
+    /// learn them from the gossip graph. Afterwards, they are changed by consensus on
+    /// `Observation::Add`.
+    pub fn from_weighted_genesis(
+        our_id: S,
+        genesis_weights: &BTreeMap<S::PublicId, u64>,
+        consensus_mode: ConsensusMode,
+    ) -> Self {
+        let genesis_group: BTreeSet<_> = genesis_weights.keys().cloned().collect();
         if !genesis_group.contains(our_id.public_id()) {
             log_or_panic!("Genesis group must contain us");
         }
 
+        let genesis_observation = Observation::Genesis {
+            group: genesis_group.clone(),
+            weights: genesis_weights
+                .iter()
+                .filter(|(_, weight)| **weight != 1)
+                .map(|(peer_id, weight)| (peer_id.clone(), *weight))
+                .collect(),
+        };
+        if !genesis_observation.has_valid_weights() {
+            log_or_panic!("Genesis weights must be between 1 and MAX_WEIGHT");
+        }
+
         let mut peer_list = PeerList::new(our_id);
         let genesis_indices: PeerIndexSet = genesis_group
             .iter()
@@ -148,7 +220,6 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         parsec.add_initial_event();
 
         // Add event carrying genesis observation.
-        let genesis_observation = Observation::Genesis(genesis_group.clone());
         let event = parsec.our_last_event_index().and_then(|self_parent| {
             parsec.new_event_from_observation(self_parent, genesis_observation)
         });
@@ -220,6 +291,141 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         parsec
     }
 
+    /// Creates a new `Parsec` for a peer that is joining an existing section, starting from the
+    /// consensused state of the section following a stable block rather than from its genesis.
+    ///
+    /// * `our_id` is the value that will identify the owning peer in the network.
+    /// * `checkpoint` is the state of the section created by its members using
+    /// [create_checkpoint](struct.Parsec.html#method.create_checkpoint). It must be signed by a
+    /// supermajority of its voters. If we are one of its voters, we can vote straight away.
+    /// Otherwise we become a voter once our addition is consensused, as with `from_existing`.
+    /// * `consensus_mode` determines how many votes are needed for an observation to become a
+    /// candidate for consensus. For more details, see [ConsensusMode](enum.ConsensusMode.html)
+    ///
+    /// The members which created the checkpoint only gossip us the events following it. Until the
+    /// first block following the checkpoint is consensused, the unknown parents of the events we
+    /// receive are assumed to precede it.
+    ///
+    /// Returns an error if the checkpoint is not signed by a supermajority of its voters or is
+    /// inconsistent.
+    pub fn from_checkpoint(
+        our_id: S,
+        checkpoint: &Checkpoint<S::PublicId>,
+        consensus_mode: ConsensusMode,
+    ) -> Result<Self> {
+        if !checkpoint.is_signed_by_supermajority() {
+            return Err(Error::InvalidCheckpoint);
+        }
+        let content = checkpoint.content();
+
+        let mut peer_list = PeerList::new(our_id);
+        for (peer_id, weight) in &content.peers {
+            if *weight == 0 || *weight > MAX_WEIGHT {
+                return Err(Error::InvalidCheckpoint);
+            }
+            let state = if content.voters.contains(peer_id) {
+                PeerState::VOTE | PeerState::SEND
+            } else {
+                PeerState::inactive()
+            };
+            let peer_index = if peer_id == peer_list.our_pub_id() {
+                PeerIndex::OUR
+            } else {
+                peer_list.add_peer(peer_id.clone(), state)
+            };
+            peer_list.set_peer_weight(peer_index, *weight);
+        }
+
+        let voters: PeerIndexSet = content
+            .voters
+            .iter()
+            .map(|peer_id| peer_list.get_index(peer_id).ok_or(Error::InvalidCheckpoint))
+            .collect::<Result<_>>()?;
+        let consensus_history = content
+            .consensus_history
+            .iter()
+            .map(|key| key.to_key(&peer_list).ok_or(Error::InvalidCheckpoint))
+            .collect::<Result<_>>()?;
+
+        let mut parsec = Self::empty(peer_list, voters, consensus_mode);
+        parsec.graph = Graph::new_from_checkpoint(checkpoint.frontier());
+        parsec.meta_election.consensus_history = consensus_history;
+        parsec.meta_election.last_block_hash = content.last_block_hash;
+        parsec.meta_election.decided_elections = content.decided_elections;
+        parsec
+            .meta_election
+            .initialise_round_hashes(parsec.peer_list.all_ids());
+
+        let round_hashes: BTreeMap<_, _> = parsec
+            .peer_list
+            .iter()
+            .filter_map(|(peer_index, peer)| {
+                let round_hash = parsec.meta_election.round_hashes.get(peer_index)?.first()?;
+                Some((peer.id().clone(), *round_hash.value()))
+            })
+            .collect();
+        if round_hashes != content.round_hashes {
+            return Err(Error::InvalidCheckpoint);
+        }
+
+        if parsec.voters().contains(PeerIndex::OUR) {
+            parsec
+                .peer_list
+                .change_peer_state(PeerIndex::OUR, PeerState::active());
+            parsec.add_initial_event();
+        } else {
+            parsec
+                .peer_list
+                .change_peer_state(PeerIndex::OUR, PeerState::RECV);
+        }
+
+        Ok(parsec)
+    }
+
+    /// Recreates the `Parsec` instance previously saved by
+    /// [snapshot](struct.Parsec.html#method.snapshot).
+    ///
+    /// * `our_id` is the value that identified the owning peer when the snapshot was taken.
+    /// * `bytes` is the output of `snapshot`.
+    ///
+    /// The restored instance continues exactly where the original one stopped, including the
+    /// blocks not yet returned by `poll`, so it can keep gossiping without forking its own events.
+    /// Only the gossip exchanges split over several messages which were in progress are abandoned.
+    /// The limit set by `set_max_gossip_size`, the strategy set by `set_gossip_strategy` and the
+    /// coin set by `set_common_coin` have to be set again; in particular, a restored instance
+    /// tosses a [HashCoin](struct.HashCoin.html) until then.
+    ///
+    /// Returns an error if the snapshot is malformed, was taken by a different peer, or was
+    /// written in an unsupported format version.
+    pub fn restore(our_id: S, bytes: &[u8]) -> Result<Self> {
+        let (version, contents): (u32, Vec<u8>) =
+            serialisation::deserialise(bytes).map_err(|_| Error::InvalidSnapshot)?;
+        if version != SNAPSHOT_VERSION {
+            return Err(Error::UnsupportedSnapshotVersion(version));
+        }
+
+        let snapshot: Snapshot<T, S::PublicId> =
+            serialisation::deserialise(&contents).map_err(|_| Error::InvalidSnapshot)?;
+        let (our_peer, peers) = snapshot.peer_list;
+        let peer_list = PeerList::restore(our_id, our_peer, peers).ok_or(Error::InvalidSnapshot)?;
+
+        let mut parsec = Self::empty(peer_list, PeerIndexSet::default(), snapshot.consensus_mode);
+        parsec.graph = snapshot.graph;
+        parsec.observations = snapshot.observations;
+        parsec.consensused_blocks = snapshot.consensused_blocks;
+        parsec.meta_election = snapshot.meta_election;
+        #[cfg(feature = "malice-detection")]
+        {
+            parsec.pending_accusations = snapshot.pending_accusations;
+        }
+        parsec.pending_events = snapshot.pending_events;
+        parsec.orphan_events = snapshot.orphan_events;
+        parsec.published_coin_shares = snapshot.published_coin_shares;
+        parsec.checkpoint_frontiers = snapshot.checkpoint_frontiers;
+        parsec.added_peers = snapshot.added_peers;
+        Ok(parsec)
+    }
+
     // Construct empty `Parsec` with no peers (except us) and no gossip events.
     fn empty(
         peer_list: PeerList<S>,
@@ -228,16 +434,33 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     ) -> Self {
         dump_graph::init();
 
+        if !consensus_mode.is_valid() {
+            log_or_panic!("Consensus mode threshold must be below 100%");
+        }
+
         Self {
             peer_list,
             graph: Graph::new(),
             consensused_blocks: VecDeque::new(),
+            consensus_listener: None,
             observations: BTreeMap::new(),
             meta_election: MetaElection::new(genesis_group),
             consensus_mode,
             #[cfg(feature = "malice-detection")]
             pending_accusations: vec![],
             pending_events: vec![],
+            journal: None,
+            max_gossip_size: None,
+            chunked_syncs: PeerIndexMap::new(),
+            peer_protocol_versions: PeerIndexMap::new(),
+            orphan_events: OrphanPool::new(),
+            gossip_strategy: GossipStrategy::default(),
+            last_gossip_recipient: None,
+            counters: Counters::default(),
+            common_coin: Box::new(HashCoin),
+            published_coin_shares: BTreeSet::new(),
+            checkpoint_frontiers: PeerIndexMap::new(),
+            added_peers: PeerIndexSet::default(),
 
             #[cfg(any(test, feature = "testing"))]
             ignore_process_events: false,
@@ -249,29 +472,135 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         self.peer_list.our_pub_id()
     }
 
+    /// Registers `journal` to record every subsequent input to the methods voting, gossiping,
+    /// pruning or creating checkpoints, replacing any previously registered one. For more details,
+    /// including the calls which aren't journalled, see [Journal](trait.Journal.html).
+    ///
+    /// The journal is not part of a [snapshot](struct.Parsec.html#method.snapshot), so it has to
+    /// be registered again on the restored instance.
+    pub fn set_journal<J>(&mut self, journal: J)
+    where
+        J: Journal<T, S::PublicId> + Send + 'static,
+    {
+        self.journal = Some(Box::new(journal));
+    }
+
+    /// Handles the previously journalled `entries` in order, as if the corresponding methods were
+    /// called again, without appending them to the registered journal. This should be called on
+    /// an instance created with the same arguments as the one the entries were recorded by.
+    ///
+    /// The inputs which failed originally fail again and are skipped. The blocks consensused
+    /// during the replay are returned by `poll` again, so the ones already handled by the
+    /// application have to be skipped by it.
+    pub fn replay<I>(&mut self, entries: I)
+    where
+        I: IntoIterator<Item = JournalEntry<T, S::PublicId>>,
+    {
+        let journal = self.journal.take();
+
+        for entry in entries {
+            if let Err(error) = self.handle_journal_entry(entry) {
+                debug!(
+                    "{:?} failed to replay journal entry: {:?}",
+                    self.our_pub_id(),
+                    error
+                );
+            }
+        }
+
+        self.journal = journal;
+    }
+
     /// Inserts the owning peer's vote for `observation` into the gossip graph. The subsequent
     /// gossip messages will spread the vote to other peers, eventually making it a candidate for
     /// the next consensused block.
     ///
     /// Returns an error if the owning peer is not a full member of the section yet, if it has
-    /// already voted for this `observation`, or if adding a gossip event containing the vote to
+    /// already voted for this `observation`, if `observation` is an `Observation::Rekey` with an
+    /// invalid proof, if it gives a peer a voting weight of zero or above
+    /// [MAX_WEIGHT](constant.MAX_WEIGHT.html), or if adding a gossip event containing the vote to
     /// the gossip graph failed.
     pub fn vote_for(&mut self, observation: Observation<T, S::PublicId>) -> Result<()> {
         debug!("{:?} voting for {:?}", self.our_pub_id(), observation);
 
-        self.confirm_self_state(PeerState::VOTE)?;
+        self.append_to_journal(|| JournalEntry::Vote(observation.clone()));
 
-        if self.have_voted_for(&observation) {
-            return Err(Error::DuplicateVote);
+        self.cast_vote(observation, None, None)
+    }
+
+    /// Same as [vote_for](struct.Parsec.html#method.vote_for), but the vote is consensused
+    /// according to `consensus_mode` rather than to the consensus mode the instance was created
+    /// with. The mode is carried by the vote, so all the peers handle it the same way.
+    ///
+    /// Votes for the same `OpaquePayload` with different modes are consensused separately, and
+    /// the resulting blocks record the mode in
+    /// [Block::consensus_mode](struct.Block.html#method.consensus_mode). The mode is ignored for
+    /// the other observations, which always need a supermajority.
+    ///
+    /// Returns `Error::InvalidConsensusMode` if `consensus_mode` is a threshold of 100% or more.
+    pub fn vote_for_with_mode(
+        &mut self,
+        observation: Observation<T, S::PublicId>,
+        consensus_mode: ConsensusMode,
+    ) -> Result<()> {
+        debug!(
+            "{:?} voting for {:?} with {:?}",
+            self.our_pub_id(),
+            observation,
+            consensus_mode
+        );
+
+        self.append_to_journal(|| JournalEntry::VoteWithMode(observation.clone(), consensus_mode));
+
+        self.cast_vote(observation, Some(consensus_mode), None)
+    }
+
+    /// Same as [vote_for](struct.Parsec.html#method.vote_for), but the vote expires unless its
+    /// payload is consensused within the next `ttl` blocks.
+    ///
+    /// The blocks are counted from the last one consensused by us at the time of the vote, and the
+    /// expiry is carried by the vote, so all the peers drop the expired vote at the same point of
+    /// the consensus history. Once all the votes for a payload expired, the payload no longer counts
+    /// as unconsensused in
+    /// [has_unconsensused_observations](struct.Parsec.html#method.has_unconsensused_observations),
+    /// and we can vote for it again.
+    ///
+    /// Returns `Error::InvalidTtl` if `ttl` is zero.
+    pub fn vote_for_with_ttl(
+        &mut self,
+        observation: Observation<T, S::PublicId>,
+        ttl: usize,
+    ) -> Result<()> {
+        debug!(
+            "{:?} voting for {:?} with a time-to-live of {} blocks",
+            self.our_pub_id(),
+            observation,
+            ttl
+        );
+
+        if ttl == 0 {
+            return Err(Error::InvalidTtl);
         }
 
-        self.flush_pending_events()?;
+        self.append_to_journal(|| JournalEntry::VoteWithTtl(observation.clone(), ttl));
 
-        let self_parent = self.our_last_event_index()?;
-        let event = self.new_event_from_observation(self_parent, observation)?;
+        self.cast_vote(observation, None, Some(ttl))
+    }
 
-        let _ = self.add_event(event)?;
-        Ok(())
+    /// Switches the owning peer to sign its gossip events and votes with `new_id`. This is to be
+    /// called once a block with `Observation::Rekey` rotating our key to the public id of `new_id`
+    /// has been consensused. Until then, the other peers keep accepting our old signatures.
+    ///
+    /// The gossip events keep identifying us by the id we joined the section with, while the
+    /// proofs of the blocks carry the id each vote was signed with.
+    ///
+    /// Returns `Error::UnknownPeer` if no such rotation has been consensused.
+    pub fn switch_to_rekeyed_id(&mut self, new_id: S) -> Result<()> {
+        if self.peer_list.set_our_id(new_id) {
+            Ok(())
+        } else {
+            Err(Error::UnknownPeer)
+        }
     }
 
     /// Returns an iterator with the IDs of peers who the owning peer can send gossip messages to.
@@ -283,34 +612,94 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             .map(|(_, peer)| peer.id())
     }
 
+    /// Sets the policy used by
+    /// [next_gossip_recipient](struct.Parsec.html#method.next_gossip_recipient). By default, the
+    /// recipients are picked at random.
+    pub fn set_gossip_strategy(&mut self, strategy: GossipStrategy) {
+        self.gossip_strategy = strategy;
+    }
+
+    /// Returns the peer the owning peer should send its next gossip request to, chosen among the
+    /// ones returned by [gossip_recipients](struct.Parsec.html#method.gossip_recipients) according
+    /// to the strategy set by [set_gossip_strategy](struct.Parsec.html#method.set_gossip_strategy),
+    /// or `None` if there is no such peer. For more details, see
+    /// [GossipStrategy](enum.GossipStrategy.html).
+    ///
+    /// `rng` is only used by `GossipStrategy::Random`.
+    pub fn next_gossip_recipient<R: Rng>(&mut self, rng: &mut R) -> Option<S::PublicId> {
+        let recipients = self.peer_list.gossip_recipients().collect_vec();
+
+        let recipient = match self.gossip_strategy {
+            GossipStrategy::Random => rng.choose(&recipients),
+            GossipStrategy::RoundRobin => {
+                let last = self.last_gossip_recipient;
+                recipients
+                    .iter()
+                    .find(|(peer_index, _)| last.map_or(true, |last| *peer_index > last))
+                    .or_else(|| recipients.first())
+            }
+            GossipStrategy::LeastRecentlySynced => recipients
+                .iter()
+                .min_by_key(|(_, peer)| peer.last_gossiped_event()),
+            GossipStrategy::MostMissingEvents => recipients.iter().max_by_key(|(peer_index, _)| {
+                (
+                    self.estimate_events_missing_from(*peer_index),
+                    cmp::Reverse(*peer_index),
+                )
+            }),
+        };
+
+        let (peer_index, peer) = recipient?;
+        let peer_id = peer.id().clone();
+        self.last_gossip_recipient = Some(*peer_index);
+        Some(peer_id)
+    }
+
+    /// Limits the total serialised size of the gossip events in a single `Request` or `Response`
+    /// to `max_size` bytes, or removes the limit if `None`. The limit is not enforced for single
+    /// events bigger than that. By default, there is no limit.
+    ///
+    /// A gossip exchange which exceeds the limit is split over several messages, each of which has
+    /// to be handled by the recipient before the next one is created. See
+    /// [has_unfinished_gossip_with](struct.Parsec.html#method.has_unfinished_gossip_with) for
+    /// details.
+    ///
+    /// All the peers in the section have to run a version of this crate which supports split
+    /// gossip exchanges, but they don't need to use the same limit.
+    pub fn set_max_gossip_size(&mut self, max_size: Option<usize>) {
+        self.max_gossip_size = max_size;
+    }
+
+    /// Returns whether the gossip exchange with `peer_id` has been split over several messages
+    /// and not all of them have been exchanged yet. In that case, the owning peer should continue
+    /// the exchange by calling `create_gossip` for `peer_id` again once the `Response` to the
+    /// previous `Request` has been handled. The gossip events of each message are added to the
+    /// graph and used for consensus as soon as the message is handled, but the peers only know all
+    /// the events of each other once the exchange is finished.
+    pub fn has_unfinished_gossip_with(&self, peer_id: &S::PublicId) -> bool {
+        self.peer_list
+            .get_index(peer_id)
+            .and_then(|peer_index| self.chunked_syncs.get(peer_index))
+            .map(ChunkedSync::is_unfinished)
+            .unwrap_or(false)
+    }
+
     /// Creates a new message to be gossiped to a peer, containing all gossip events this peer
     /// thinks that peer needs.  If the given peer is not an active node, an error is returned.
     ///
+    /// If the gossip exchange with the peer is unfinished (see
+    /// [has_unfinished_gossip_with](struct.Parsec.html#method.has_unfinished_gossip_with)), the
+    /// message continues it instead.
+    ///
     /// * `peer_id`: the intended recipient of the gossip message
     /// * returns a `Request` to be sent to the intended recipient
     pub fn create_gossip(&mut self, peer_id: &S::PublicId) -> Result<Request<T, S::PublicId>> {
-        let peer_index = self.get_peer_index(peer_id)?;
-        self.confirm_allowed_to_gossip_to(peer_index)?;
-
-        debug!(
-            "{:?} creating gossip request for {:?}",
-            self.our_pub_id(),
-            peer_id
-        );
-
-        let self_parent = self.peer_list.last_event(PeerIndex::OUR).ok_or_else(|| {
-            log_or_panic!("{:?} missing our own last event hash.", self.our_pub_id());
-            Error::Logic
-        })?;
-        let sync_event = Event::new_from_requesting(self_parent, peer_id, self.event_context())?;
-        let _ = self.add_event(sync_event)?;
+        self.append_to_journal(|| JournalEntry::CreateGossip(peer_id.clone()));
 
-        let events = if self.peer_list.last_event(peer_index).is_some() {
-            self.events_to_gossip_to_peer(peer_index)?
-        } else {
-            self.graph.iter().map(|e| e.inner()).collect()
-        };
-        self.pack_events(events).map(Request::new)
+        let mut request = self.create_request(peer_id)?;
+        request.version = self.protocol_version_for(peer_id);
+        self.counters.gossip_bytes_sent += serialisation::serialised_size(&request);
+        Ok(request)
     }
 
     /// Handles a `Request` the owning peer received from the `src` peer.  Returns a `Response` to
@@ -327,15 +716,15 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             src
         );
 
-        let src_index = self.get_peer_index(src)?;
-        let other_parent = self.unpack_and_add_events(src_index, req.packed_events)?;
-        #[cfg(feature = "malice-detection")]
-        self.create_accusation_events(other_parent)?;
-        self.create_sync_event(true, other_parent)?;
-        self.flush_pending_events()?;
+        self.append_to_journal(|| JournalEntry::Request(src.clone(), req.clone()));
+        self.counters.gossip_bytes_received += serialisation::serialised_size(&req);
 
-        let events = self.events_to_gossip_to_peer(src_index)?;
-        self.pack_events(events).map(Response::new)
+        // Respond in the version of the request, which the peer obviously supports.
+        let version = req.version;
+        let mut response = self.create_response(src, req)?;
+        response.version = version;
+        self.counters.gossip_bytes_sent += serialisation::serialised_size(&response);
+        Ok(response)
     }
 
     /// Handles a `Response` the owning peer received from the `src` peer. Returns `Err` if the
@@ -351,14 +740,186 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             src
         );
 
+        self.append_to_journal(|| JournalEntry::Response(src.clone(), resp.clone()));
+        self.counters.gossip_bytes_received += serialisation::serialised_size(&resp);
+
         let src_index = self.get_peer_index(src)?;
+        self.confirm_supported_version(src_index, resp.version, resp.max_version)?;
+        if resp.packed_events.is_empty() {
+            // The peer acknowledges a part of our request.
+            return match self.chunked_syncs.get_mut(src_index) {
+                Some(ref mut sync) if sync.unacknowledged_chunks > 0 => {
+                    sync.unacknowledged_chunks -= 1;
+                    Ok(())
+                }
+                _ => Err(Error::InvalidMessage),
+            };
+        }
+
         let other_parent = self.unpack_and_add_events(src_index, resp.packed_events)?;
+        if resp.has_more {
+            // Wait for the rest of the response before creating our sync event.
+            self.chunked_syncs
+                .entry(src_index)
+                .or_insert_with(ChunkedSync::new)
+                .awaiting_response = true;
+            return Ok(());
+        }
+
         #[cfg(feature = "malice-detection")]
         self.create_accusation_events(other_parent)?;
         self.create_sync_event(false, other_parent)?;
+        self.create_coin_share_events()?;
         self.flush_pending_events()
     }
 
+    /// Returns a `Fetch` message asking `peer_id` for the events we are missing to insert the ones
+    /// it sent us before their parents, e.g. if handling its `Request` or `Response` failed with
+    /// `Error::UnknownSelfParent` or `Error::UnknownOtherParent`. Returns `None` if we aren't
+    /// missing any event from that peer.
+    ///
+    /// The events received early are kept until their parents arrive, so once the events returned
+    /// by the peer are handled using [handle_fetched](struct.Parsec.html#method.handle_fetched),
+    /// the failed message doesn't need to be handled again.
+    pub fn create_fetch(&mut self, peer_id: &S::PublicId) -> Option<Fetch> {
+        let peer_index = self.peer_list.get_index(peer_id)?;
+        let hashes: Vec<_> = self
+            .orphan_events
+            .missing_parents(peer_index)
+            .into_iter()
+            .filter(|hash| !self.graph.contains(hash))
+            .take(MAX_FETCHED_HASHES)
+            .collect();
+        if hashes.is_empty() {
+            return None;
+        }
+
+        let mut fetch = Fetch::new(hashes);
+        fetch.version = self.protocol_version_for(peer_id);
+        self.counters.gossip_bytes_sent += serialisation::serialised_size(&fetch);
+        Some(fetch)
+    }
+
+    /// Handles a `Fetch` the owning peer received from the `src` peer. Returns the requested events
+    /// we know of together with their ancestors `src` isn't known to have, in topological order,
+    /// to be sent back to `src`. They are split over several responses if they exceed the limit
+    /// set by [set_max_gossip_size](struct.Parsec.html#method.set_max_gossip_size).
+    ///
+    /// Returns `Error::InvalidMessage` if the fetch asks for more events than a peer can be missing.
+    pub fn handle_fetch(
+        &mut self,
+        src: &S::PublicId,
+        fetch: Fetch,
+    ) -> Result<Vec<FetchResponse<T, S::PublicId>>> {
+        self.counters.gossip_bytes_received += serialisation::serialised_size(&fetch);
+
+        let src_index = self.get_peer_index(src)?;
+        self.confirm_allowed_to_gossip_to(src_index)?;
+        self.confirm_supported_version(src_index, fetch.version, fetch.max_version)?;
+        if fetch.hashes.len() > MAX_FETCHED_HASHES {
+            return Err(Error::InvalidMessage);
+        }
+
+        let last_known = self
+            .peer_list
+            .last_event(src_index)
+            .and_then(|event_index| self.graph.get(event_index));
+        let events: BTreeMap<_, _> = fetch
+            .hashes
+            .iter()
+            .filter_map(|hash| self.graph.get_by_hash(hash))
+            .flat_map(|event| self.graph.ancestors(event))
+            .filter(|event| {
+                last_known.map_or(true, |last_known| !last_known.is_descendant_of(*event))
+            })
+            .map(|event| (event.topological_index(), event.inner()))
+            .collect();
+        let packed_events = self.pack_events(events.values().cloned())?;
+
+        // Respond in the version of the fetch, which the peer obviously supports.
+        let responses: Vec<_> = self
+            .split_gossip(packed_events)
+            .into_iter()
+            .map(|packed_events| {
+                let mut response = FetchResponse::new(packed_events);
+                response.version = fetch.version;
+                response
+            })
+            .collect();
+        self.counters.gossip_bytes_sent += responses
+            .iter()
+            .map(serialisation::serialised_size)
+            .sum::<u64>();
+        Ok(responses)
+    }
+
+    /// Handles one of the responses the owning peer received from the `src` peer to a `Fetch`.
+    /// Returns `Err` if any of its events is invalid, or if it's still missing some of their
+    /// ancestors, in which case they can be fetched using [create_fetch](#method.create_fetch)
+    /// again, e.g. once all the responses are handled.
+    pub fn handle_fetched(
+        &mut self,
+        src: &S::PublicId,
+        response: FetchResponse<T, S::PublicId>,
+    ) -> Result<()> {
+        debug!(
+            "{:?} received fetched events from {:?}",
+            self.our_pub_id(),
+            src
+        );
+
+        self.append_to_journal(|| JournalEntry::Fetched(src.clone(), response.clone()));
+        self.counters.gossip_bytes_received += serialisation::serialised_size(&response);
+
+        let src_index = self.get_peer_index(src)?;
+        self.confirm_supported_version(src_index, response.version, response.max_version)?;
+        let _ = self.unpack_and_add_events(src_index, response.packed_events)?;
+        Ok(())
+    }
+
+    /// Replaces the source of the coin tossed by the meta-elections, which is
+    /// [HashCoin](struct.HashCoin.html) by default. All the peers of the section must use the same
+    /// kind of coin, so this should be called right after the instance is created. For more
+    /// details, see [CommonCoin](trait.CommonCoin.html).
+    ///
+    /// The coin is not part of a [snapshot](struct.Parsec.html#method.snapshot), so it has to be
+    /// set again on the restored instance.
+    pub fn set_common_coin<C>(&mut self, coin: C)
+    where
+        C: CommonCoin<S::PublicId> + Send + 'static,
+    {
+        self.common_coin = Box::new(coin);
+    }
+
+    /// Enables or disables the joint-consensus mode, which is disabled by default. In this mode,
+    /// the meta-election following a change of the voters is a transition window: the
+    /// supermajorities it relies on must be reached among both the previous and the new voters,
+    /// rather than among the new ones only.
+    ///
+    /// All the peers of the section must use the same mode, so this should be called right after
+    /// the instance is created.
+    pub fn set_joint_consensus(&mut self, enabled: bool) {
+        self.meta_election.set_joint_consensus(enabled);
+    }
+
+    /// Registers `listener` to receive the blocks as soon as they are consensused, replacing any
+    /// previously registered one. The blocks consensused but not yet returned by `poll` are passed
+    /// to it immediately. From then on, `poll` always returns `None`.
+    ///
+    /// The listener is not part of a [snapshot](struct.Parsec.html#method.snapshot), so it has to
+    /// be registered again on the restored instance.
+    pub fn set_consensus_listener<L>(&mut self, listener: L)
+    where
+        L: ConsensusListener<T, S::PublicId> + Send + 'static,
+    {
+        let mut listener = Box::new(listener);
+        for block_group in self.consensused_blocks.drain(..) {
+            listener.handle_consensus(block_group.blocks.into(), block_group.election);
+        }
+
+        self.consensus_listener = Some(listener);
+    }
+
     /// Returns the next stable block, if any. The method might need to be called more than once
     /// for the caller to get all the blocks that have been consensused. A `None` value means that
     /// all the blocks consensused so far have already been returned.
@@ -366,6 +927,9 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     /// Once the owning peer has been removed from the section (i.e. a block with payload
     /// `Observation::Remove(our_id)` has been made stable), then no further blocks will be
     /// enqueued. So, once `poll()` returns such a block, it will continue to return `None` forever.
+    ///
+    /// If a [ConsensusListener](trait.ConsensusListener.html) is registered, the blocks are passed
+    /// to it instead and this method always returns `None`.
     pub fn poll(&mut self) -> Option<Block<T, S::PublicId>> {
         let mut block_group = self.batch_poll()?;
         let block = block_group.pop_front()?;
@@ -375,6 +939,27 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         Some(block)
     }
 
+    /// Returns the next group of stable blocks, if any, together with the information about the
+    /// meta-election they were decided in. The blocks of a group were all decided at the same time
+    /// and are in their consensus order. The method might need to be called more than once for the
+    /// caller to get all the groups that have been consensused. A `None` value means that all the
+    /// groups consensused so far have already been returned.
+    ///
+    /// Calls to `poll()` and `poll_group()` can be mixed. If `poll()` has already returned some of
+    /// the blocks of a group, `poll_group()` returns only the remaining ones.
+    ///
+    /// Once the owning peer has been removed from the section (i.e. a block with payload
+    /// `Observation::Remove(our_id)` has been made stable), then no further blocks will be
+    /// enqueued. So, once `poll_group()` returns such a block, it will continue to return `None`
+    /// forever.
+    ///
+    /// If a [ConsensusListener](trait.ConsensusListener.html) is registered, the groups are passed
+    /// to it instead and this method always returns `None`.
+    pub fn poll_group(&mut self) -> Option<(Vec<Block<T, S::PublicId>>, ElectionInfo)> {
+        self.batch_poll()
+            .map(|block_group| (block_group.blocks.into(), block_group.election))
+    }
+
     /// Returns the next group of stable blocks, if any. The method might need to be called more
     /// than once for the caller to get all the blocks that have been consensused. A `None` value
     /// means that all the blocks consensused so far have already been returned.
@@ -393,21 +978,63 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         self.peer_list.our_state().can_vote()
     }
 
-    /// Checks if the given `observation` has already been voted for by the owning peer.
+    /// Checks if the given `observation` has already been voted for by the owning peer, with any
+    /// consensus mode, and the votes for it haven't all expired.
     pub fn have_voted_for(&self, observation: &Observation<T, S::PublicId>) -> bool {
         let hash = ObservationHash::from(observation);
-        let key = ObservationKey::new(hash, PeerIndex::OUR, self.consensus_mode.of(observation));
         self.observations
-            .get(&key)
-            .map(|info| info.created_by_us)
-            .unwrap_or(false)
+            .iter()
+            .any(|(key, info)| *key.hash() == hash && info.created_by_us && !info.expired)
     }
 
     /// Check if there are any observations that have been voted for but not yet consensused - i.e.
     /// if there is a gossip event containing a vote for a payload that is not yet a part of a
-    /// stable block.
+    /// stable block. Observations whose votes all expired are not included.
     pub fn has_unconsensused_observations(&self) -> bool {
-        self.observations.values().any(|info| !info.consensused)
+        self.observations
+            .values()
+            .any(|info| !info.consensused && !info.expired)
+    }
+
+    /// Returns the hash of the last block consensused so far, including the blocks not yet returned
+    /// by `poll`, or `None` if there is none yet. The next consensused block is chained to it. For
+    /// more details, see [Block::hash](struct.Block.html#method.hash).
+    pub fn last_block_hash(&self) -> Option<&BlockHash> {
+        self.meta_election.last_block_hash()
+    }
+
+    /// Returns counters describing the state and the activity of this instance. For more details,
+    /// see [Stats](struct.Stats.html).
+    pub fn stats(&self) -> Stats<S::PublicId> {
+        let meta_votes = self
+            .meta_election
+            .meta_vote_progress()
+            .iter()
+            .filter_map(|(peer_index, (round, step))| {
+                self.peer_list.get(peer_index).map(|peer| {
+                    let progress = MetaVoteProgress {
+                        round: *round,
+                        step: step.index(),
+                    };
+                    (peer.id().clone(), progress)
+                })
+            })
+            .collect();
+
+        Stats {
+            graph_size: self.graph.len(),
+            pending_events: self.pending_events.len(),
+            unconsensused_observations: self
+                .observations
+                .values()
+                .filter(|info| !info.consensused)
+                .count(),
+            meta_votes,
+            coin_tosses: self.counters.coin_tosses,
+            gossip_bytes_sent: self.counters.gossip_bytes_sent,
+            gossip_bytes_received: self.counters.gossip_bytes_received,
+            accusations_raised: self.counters.accusations_raised,
+        }
     }
 
     /// Returns observations voted for by the owning peer which haven't been returned as a stable
@@ -421,46 +1048,425 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             .chain(self.our_unconsensused_observations())
     }
 
-    fn our_consensused_observations(&self) -> impl Iterator<Item = &Observation<T, S::PublicId>> {
-        self.observations.values().filter_map(move |info| {
-            if info.created_by_us
-                && info.consensused
-                && self.has_our_unpolled_blocks(&info.observation)
-            {
-                Some(&info.observation)
-            } else {
-                None
-            }
-        })
-    }
+    /// Serialises the whole state of this instance: the gossip graph, the peer list, the
+    /// observations, the meta-election, the consensused blocks not yet returned by `poll`, the
+    /// events still waiting for their parents, the coin shares already published and the
+    /// checkpoints created for joining peers. The secret ID of the owning peer and the settings
+    /// listed in [restore](struct.Parsec.html#method.restore) are not included.
+    ///
+    /// The returned bytes are prefixed with a format version and can be turned back into a
+    /// `Parsec` instance using [restore](struct.Parsec.html#method.restore).
+    pub fn snapshot(&self) -> Vec<u8> {
+        let snapshot = SnapshotRef {
+            peer_list: &self.peer_list,
+            graph: &self.graph,
+            observations: &self.observations,
+            consensused_blocks: &self.consensused_blocks,
+            meta_election: &self.meta_election,
+            consensus_mode: self.consensus_mode,
+            #[cfg(feature = "malice-detection")]
+            pending_accusations: &self.pending_accusations,
+            pending_events: &self.pending_events,
+            orphan_events: &self.orphan_events,
+            published_coin_shares: &self.published_coin_shares,
+            checkpoint_frontiers: &self.checkpoint_frontiers,
+            added_peers: &self.added_peers,
+        };
 
-    fn our_unconsensused_observations(&self) -> impl Iterator<Item = &Observation<T, S::PublicId>> {
-        self.observations.values().filter_map(|info| {
-            if info.created_by_us && !info.consensused {
-                Some(&info.observation)
+        match serialisation::serialise(&snapshot)
+            .and_then(|contents| serialisation::serialise(&(SNAPSHOT_VERSION, contents)))
+        {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                log_or_panic!(
+                    "{:?} failed to serialise snapshot: {:?}",
+                    self.our_pub_id(),
+                    error
+                );
+                vec![]
+            }
+        }
+    }
+
+    /// Creates a checkpoint of the consensused state of the section following the last block
+    /// consensused so far, signed by us, from which a joining peer can start using
+    /// [from_checkpoint](struct.Parsec.html#method.from_checkpoint) instead of receiving the whole
+    /// gossip history.
+    ///
+    /// The checkpoints created by the members which consensused the same blocks are identical
+    /// except for their signatures, which can be gathered using
+    /// [Checkpoint::merge](struct.Checkpoint.html#method.merge) until a supermajority of the voters
+    /// signed it.
+    ///
+    /// The voters of the checkpoint we haven't received any gossip from yet are expected to join
+    /// from it, so from now on we only gossip the events following the checkpoint to them.
+    ///
+    /// Returns an error if we are not a voter.
+    pub fn create_checkpoint(&mut self) -> Result<Checkpoint<S::PublicId>> {
+        self.append_to_journal(|| JournalEntry::CreateCheckpoint);
+
+        self.confirm_self_state(PeerState::VOTE)?;
+
+        // None of the ancestors of our last event preceding the first event carrying a payload
+        // not consensused yet can affect the meta-elections following the checkpoint.
+        let new_consensus_start_index = self.meta_election.new_consensus_start_index();
+        let frontier = self
+            .peer_list
+            .our_events()
+            .rev()
+            .find(|event_index| event_index.topological_index() < new_consensus_start_index);
+        let frontier_hashes = if let Some(frontier) = frontier {
+            // Only the peers added after the section started can be joining: the genesis voters
+            // we haven't heard from yet are merely slow and still need our whole history.
+            let joining_peers: Vec<_> = self
+                .voters()
+                .iter()
+                .filter(|peer_index| {
+                    self.added_peers.contains(*peer_index)
+                        && self.peer_list.last_event(*peer_index).is_none()
+                })
+                .collect();
+            for peer_index in joining_peers {
+                let _ = self.checkpoint_frontiers.insert(peer_index, frontier);
+            }
+            self.checkpoint_frontier_hashes(frontier)
+        } else {
+            BTreeSet::new()
+        };
+
+        let peers = self
+            .peer_list
+            .iter()
+            .map(|(_, peer)| (peer.id().clone(), peer.weight()))
+            .collect();
+        let voters = self
+            .voters()
+            .iter()
+            .filter_map(|peer_index| self.peer_list.get(peer_index))
+            .map(|peer| peer.id().clone())
+            .collect();
+        let consensus_history = self
+            .meta_election
+            .consensus_history()
+            .iter()
+            .filter_map(|key| ObservationKeySnapshot::new(key, &self.peer_list))
+            .collect();
+        let round_hashes = self
+            .peer_list
+            .iter()
+            .filter_map(|(peer_index, peer)| {
+                let round_hash = self.meta_election.round_hashes.get(peer_index)?.first()?;
+                Some((peer.id().clone(), *round_hash.value()))
+            })
+            .collect();
+
+        let content = CheckpointContent {
+            decided_elections: self.meta_election.decided_elections,
+            peers,
+            voters,
+            consensus_history,
+            last_block_hash: self.meta_election.last_block_hash().cloned(),
+            round_hashes,
+        };
+        Ok(Checkpoint::new(
+            content,
+            frontier_hashes,
+            self.peer_list.our_id(),
+        ))
+    }
+
+    /// Discards the gossip events which can no longer affect consensus, together with their
+    /// meta-events and the consensused observations no longer carried by any remaining event. This
+    /// bounds the memory used by long-running instances and can be called at any time, e.g. after
+    /// each `poll`.
+    ///
+    /// Only events preceding the first event carrying a not yet consensused payload are
+    /// considered. Of those, the events which some peer might still need, or that might still
+    /// become parents of new events, are kept. Nothing is discarded while there is a peer we
+    /// haven't received any gossip from yet.
+    ///
+    /// Note that a pruned instance can no longer send the full gossip history to peers joining
+    /// the section afterwards.
+    ///
+    /// Returns the number of discarded events.
+    pub fn prune(&mut self) -> usize {
+        self.append_to_journal(|| JournalEntry::Prune);
+
+        let end_index = self.prune_end_index();
+        if end_index <= self.graph.start_index() {
+            return 0;
+        }
+
+        self.peer_list.prune_events(end_index);
+        self.meta_election.prune(end_index);
+        let pruned = self.graph.prune(end_index);
+
+        let carried_keys: BTreeSet<_> = self
+            .graph
+            .iter()
+            .filter_map(|event| event.payload_key().cloned())
+            .collect();
+        let consensused_blocks = &self.consensused_blocks;
+        self.observations.retain(|key, info| {
+            !info.consensused
+                || carried_keys.contains(key)
+                || consensused_blocks
+                    .iter()
+                    .flatten()
+                    .any(|block| *block.payload() == info.observation)
+        });
+
+        debug!(
+            "{:?} pruned {} events from the gossip graph",
+            self.our_pub_id(),
+            pruned
+        );
+        pruned
+    }
+
+    // Topological index of the first event that must not be pruned.
+    fn prune_end_index(&self) -> usize {
+        let mut end_index = self.meta_election.new_consensus_start_index();
+
+        for (peer_index, peer) in self.peer_list.iter() {
+            let last_event = match peer.events().rev().next() {
+                Some(event_index) => event_index,
+                None if peer.state() == PeerState::inactive() => continue,
+                None => return 0,
+            };
+
+            // The last event of every peer can still become a parent of a new event.
+            end_index = cmp::min(end_index, last_event.topological_index());
+
+            if peer.state() == PeerState::inactive() {
+                continue;
+            }
+
+            // The peer must already have all the pruned events, and must know that we have them
+            // too, so it never gossips them to us again. That holds for the ancestors of our
+            // latest event the peer's last event is descendant of.
+            let last_event = if let Some(event) = self.graph.get(last_event) {
+                event
+            } else {
+                return 0;
+            };
+            let our_events: Vec<_> = if peer_index == PeerIndex::OUR {
+                vec![last_event]
+            } else if let Some(index_by_creator) = last_event.last_ancestor_by(PeerIndex::OUR) {
+                self.peer_list
+                    .events_by_index(PeerIndex::OUR, index_by_creator)
+                    .filter_map(|event_index| self.graph.get(event_index))
+                    .collect()
             } else {
-                None
+                return 0;
+            };
+
+            for our_event in our_events {
+                let first_unknown = self
+                    .graph
+                    .iter()
+                    .find(|event| !our_event.is_descendant_of(event))
+                    .map(|event| event.topological_index())
+                    .unwrap_or_else(|| self.graph.end_index());
+                end_index = cmp::min(end_index, first_unknown);
             }
-        })
+        }
+
+        end_index
     }
 
-    fn has_our_unpolled_blocks(&self, payload: &Observation<T, S::PublicId>) -> bool {
-        let mut matching_blocks = self
-            .consensused_blocks
+    // Returns our observations with blocks not yet polled, in the order of the blocks.
+    fn our_consensused_observations(&self) -> impl Iterator<Item = &Observation<T, S::PublicId>> {
+        self.consensused_blocks
             .iter()
             .flatten()
-            .filter(|block| block.payload() == payload);
-
-        // In `Supermajority` mode, check only if the payload matches, as there can be blocks not
-        // signed by us, yet with payloads voted for by us.
-        // In `Single` mode, on the other hand, check also that we signed it, to avoid false
-        // positives when there are blocks with the same payloads but signed by someone else.
-        match self.consensus_mode.of(payload) {
-            ConsensusMode::Supermajority => matching_blocks.next().is_some(),
-            ConsensusMode::Single => {
-                matching_blocks.any(|block| block.is_signed_by(self.our_pub_id()))
+            .filter_map(move |block| {
+                let key = ObservationKey::new(
+                    ObservationHash::from(block.payload()),
+                    PeerIndex::OUR,
+                    block.consensus_mode(),
+                );
+                let info = self.observations.get(&key)?;
+                // In `Supermajority` and `Threshold` modes, check only if the payload matches, as
+                // there can be blocks not signed by us, yet with payloads voted for by us.
+                // In `Single` mode, on the other hand, check also that we signed it, to avoid false
+                // positives when there are blocks with the same payloads but signed by someone
+                // else.
+                let ours = match block.consensus_mode() {
+                    ConsensusMode::Supermajority | ConsensusMode::Threshold(_) => true,
+                    ConsensusMode::Single => block.is_signed_by(self.our_pub_id()),
+                };
+                if ours && info.created_by_us && info.consensused {
+                    Some(&info.observation)
+                } else {
+                    None
+                }
+            })
+    }
+
+    // Returns our observations not consensused yet, in the order we voted for them.
+    fn our_unconsensused_observations(&self) -> impl Iterator<Item = &Observation<T, S::PublicId>> {
+        self.peer_list
+            .our_events()
+            .filter_map(move |index| self.graph.get(index))
+            .filter_map(move |event| self.observations.get(event.payload_key()?))
+            .filter_map(|info| {
+                if info.created_by_us && !info.consensused {
+                    Some(&info.observation)
+                } else {
+                    None
+                }
+            })
+    }
+
+    fn create_request(&mut self, peer_id: &S::PublicId) -> Result<Request<T, S::PublicId>> {
+        let peer_index = self.get_peer_index(peer_id)?;
+        self.confirm_allowed_to_gossip_to(peer_index)?;
+
+        if let Some(sync) = self.chunked_syncs.get_mut(peer_index) {
+            if let Some(packed_events) = sync.request_chunks.pop_front() {
+                let has_more = !sync.request_chunks.is_empty();
+                if has_more {
+                    sync.unacknowledged_chunks += 1;
+                }
+                return Ok(Request::new_chunk(packed_events, has_more));
+            }
+
+            if sync.awaiting_response {
+                // Ask the peer for the next part of its response.
+                sync.awaiting_response = false;
+                return Ok(Request::new(vec![]));
             }
         }
+
+        debug!(
+            "{:?} creating gossip request for {:?}",
+            self.our_pub_id(),
+            peer_id
+        );
+
+        let self_parent = self.peer_list.last_event(PeerIndex::OUR).ok_or_else(|| {
+            log_or_panic!("{:?} missing our own last event hash.", self.our_pub_id());
+            Error::Logic
+        })?;
+        let sync_event = Event::new_from_requesting(self_parent, peer_id, self.event_context())?;
+        let _ = self.add_event(sync_event)?;
+
+        let events = if self.peer_list.last_event(peer_index).is_some() {
+            self.events_to_gossip_to_peer(peer_index)?
+        } else {
+            self.events_to_gossip_to_new_peer(peer_index)
+        };
+        let packed_events = self.pack_events(events)?;
+
+        let mut chunks = self.split_gossip(packed_events);
+        let packed_events = chunks.pop_front().unwrap_or_default();
+        let has_more = !chunks.is_empty();
+        let sync = self
+            .chunked_syncs
+            .entry(peer_index)
+            .or_insert_with(ChunkedSync::new);
+        sync.request_chunks = chunks;
+        sync.unacknowledged_chunks = if has_more { 1 } else { 0 };
+
+        Ok(Request::new_chunk(packed_events, has_more))
+    }
+
+    fn create_response(
+        &mut self,
+        src: &S::PublicId,
+        req: Request<T, S::PublicId>,
+    ) -> Result<Response<T, S::PublicId>> {
+        let src_index = self.get_peer_index(src)?;
+        self.confirm_supported_version(src_index, req.version, req.max_version)?;
+        if req.packed_events.is_empty() {
+            // The peer asks for the next part of our response.
+            return self.next_response_chunk(src_index);
+        }
+
+        let other_parent = self.unpack_and_add_events(src_index, req.packed_events)?;
+        if req.has_more {
+            // Acknowledge this part of the request and wait for the rest before responding.
+            return Ok(Response::new(vec![]));
+        }
+
+        #[cfg(feature = "malice-detection")]
+        self.create_accusation_events(other_parent)?;
+        self.create_sync_event(true, other_parent)?;
+        self.create_coin_share_events()?;
+        self.flush_pending_events()?;
+
+        let events = self.events_to_gossip_to_peer(src_index)?;
+        let packed_events = self.pack_events(events)?;
+
+        let mut chunks = self.split_gossip(packed_events);
+        let packed_events = chunks.pop_front().unwrap_or_default();
+        let has_more = !chunks.is_empty();
+        self.chunked_syncs
+            .entry(src_index)
+            .or_insert_with(ChunkedSync::new)
+            .response_chunks = chunks;
+
+        Ok(Response::new_chunk(packed_events, has_more))
+    }
+
+    // Splits `packed_events` into chunks according to `max_gossip_size`. There is always at least
+    // one chunk.
+    fn split_gossip(
+        &self,
+        packed_events: Vec<PackedEvent<T, S::PublicId>>,
+    ) -> VecDeque<Vec<PackedEvent<T, S::PublicId>>> {
+        if let Some(max_size) = self.max_gossip_size {
+            split_into_chunks(packed_events, max_size)
+        } else {
+            iter::once(packed_events).collect()
+        }
+    }
+
+    fn next_response_chunk(&mut self, peer_index: PeerIndex) -> Result<Response<T, S::PublicId>> {
+        let sync = self
+            .chunked_syncs
+            .get_mut(peer_index)
+            .ok_or(Error::InvalidMessage)?;
+        let packed_events = sync
+            .response_chunks
+            .pop_front()
+            .ok_or(Error::InvalidMessage)?;
+        Ok(Response::new_chunk(
+            packed_events,
+            !sync.response_chunks.is_empty(),
+        ))
+    }
+
+    fn append_to_journal<F>(&mut self, entry: F)
+    where
+        F: FnOnce() -> JournalEntry<T, S::PublicId>,
+    {
+        if let Some(ref mut journal) = self.journal {
+            journal.append(&entry());
+        }
+    }
+
+    pub(crate) fn handle_journal_entry(
+        &mut self,
+        entry: JournalEntry<T, S::PublicId>,
+    ) -> Result<()> {
+        match entry {
+            JournalEntry::Vote(observation) => self.vote_for(observation),
+            JournalEntry::VoteWithMode(observation, consensus_mode) => {
+                self.vote_for_with_mode(observation, consensus_mode)
+            }
+            JournalEntry::VoteWithTtl(observation, ttl) => self.vote_for_with_ttl(observation, ttl),
+            JournalEntry::CreateGossip(recipient) => self.create_gossip(&recipient).map(|_| ()),
+            JournalEntry::Request(src, request) => self.handle_request(&src, request).map(|_| ()),
+            JournalEntry::Response(src, response) => self.handle_response(&src, response),
+            JournalEntry::Fetched(src, packed_events) => self.handle_fetched(&src, packed_events),
+            JournalEntry::Prune => {
+                let _ = self.prune();
+                Ok(())
+            }
+            JournalEntry::CreateCheckpoint => self.create_checkpoint().map(|_| ()),
+        }
     }
 
     /// Must only be used for events which have already been added to our graph.
@@ -492,6 +1498,35 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         }
     }
 
+    // Checks the message from `peer_index` is written in a protocol version we support, and
+    // records the newest version the peer advertised supporting.
+    fn confirm_supported_version(
+        &mut self,
+        peer_index: PeerIndex,
+        version: u32,
+        max_version: u32,
+    ) -> Result<()> {
+        if !is_supported_version(version) {
+            return Err(Error::IncompatibleVersion(version));
+        }
+
+        let _ = self
+            .peer_protocol_versions
+            .insert(peer_index, cmp::max(version, max_version));
+        Ok(())
+    }
+
+    // Returns the protocol version to write our messages to `peer_id` in: the newest version both
+    // of us support, or the oldest version we support until the peer advertised its versions.
+    fn protocol_version_for(&self, peer_id: &S::PublicId) -> u32 {
+        self.peer_list
+            .get_index(peer_id)
+            .and_then(|peer_index| self.peer_protocol_versions.get(peer_index))
+            .map_or(MIN_PROTOCOL_VERSION, |max_version| {
+                cmp::min(*max_version, PROTOCOL_VERSION)
+            })
+    }
+
     fn confirm_self_state(&self, required: PeerState) -> Result<()> {
         let actual = self.peer_list.our_state();
         if actual.contains(required) {
@@ -561,8 +1596,9 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             .collect()
     }
 
-    // Returns the list peers which have created forked events, and the event to use as the
-    // other-parent when creating our sync event as a result of handling this message.
+    // Returns the event to use as the other-parent when creating our sync event as a result of
+    // handling this message. Events whose parents are still unknown are kept in the orphan pool
+    // and inserted once their parents arrive, in this message or in a later one.
     fn unpack_and_add_events(
         &mut self,
         src_index: PeerIndex,
@@ -571,47 +1607,148 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         self.confirm_self_state(PeerState::RECV)?;
         self.confirm_peer_state(src_index, PeerState::SEND)?;
 
-        let hash_of_last_event = packed_events
-            .last()
+        let hashes: Vec<_> = packed_events
+            .iter()
             .map(PackedEvent::compute_hash)
-            .ok_or_else(|| Error::InvalidMessage)?;
-        for packed_event in packed_events {
-            if let Some(event) = self.unpack(packed_event)? {
-                let event_creator = event.creator();
-                let event_index = self.add_event(event)?;
-
-                // We have received an event of a peer in the message. The peer can now receive
-                // gossips from us as well.
-                self.peer_list
-                    .change_peer_state(event_creator, PeerState::RECV);
-                self.peer_list
-                    .record_gossiped_event_by(src_index, event_index);
-
-                #[cfg(feature = "malice-detection")]
-                self.detect_accomplice(event_index)?;
+            .collect();
+        let hash_of_last_event = *hashes.last().ok_or_else(|| Error::InvalidMessage)?;
+        let mut pending: BTreeSet<_> = hashes.iter().cloned().collect();
+        let mut first_error = None;
+        for (hash, packed_event) in hashes.into_iter().zip(packed_events) {
+            let _ = pending.remove(&hash);
+            match self.add_packed_event(src_index, hash, packed_event, &pending) {
+                Ok(Some(_)) => self.add_orphans_of(hash, &pending)?,
+                Ok(None) => (),
+                Err(error @ Error::UnknownSelfParent) | Err(error @ Error::UnknownOtherParent) => {
+                    let _ = first_error.get_or_insert(error);
+                }
+                Err(error) => return Err(error),
             }
         }
 
         #[cfg(feature = "malice-detection")]
         self.detect_premature_gossip()?;
 
-        let last_event_index = self
-            .graph
-            .get_index(&hash_of_last_event)
-            .ok_or_else(|| Error::InvalidMessage)?;
-        Ok(last_event_index)
+        match self.graph.get_index(&hash_of_last_event) {
+            Some(last_event_index) => Ok(last_event_index),
+            None => Err(first_error.unwrap_or_else(|| {
+                if self.orphan_events.contains(&hash_of_last_event) {
+                    Error::UnknownOtherParent
+                } else {
+                    Error::InvalidMessage
+                }
+            })),
+        }
+    }
+
+    // Inserts `packed_event` received from `src_index` into the graph. If some of its parents are
+    // unknown yet, buffers it in the orphan pool instead and returns `Ok(None)` if they are among
+    // the `pending` events of the current message or already buffered, otherwise the error
+    // unpacking it failed with.
+    fn add_packed_event(
+        &mut self,
+        src_index: PeerIndex,
+        hash: EventHash,
+        packed_event: PackedEvent<T, S::PublicId>,
+        pending: &BTreeSet<EventHash>,
+    ) -> Result<Option<EventIndex>> {
+        let awaiting_parent = packed_event.parents().any(|parent| {
+            !self.graph.contains(parent)
+                && (pending.contains(parent) || self.orphan_events.contains(parent))
+        });
+        if awaiting_parent {
+            // Events forged or created under throwaway ids mustn't push genuine ones out of the
+            // bounded orphan pool.
+            Event::verify_packed_signature(&packed_event, &self.peer_list)?;
+            self.orphan_events.insert(hash, src_index, packed_event);
+            return Ok(None);
+        }
+
+        let event = match self.unpack(packed_event.clone()) {
+            Ok(Some(event)) => event,
+            Ok(None) => return Ok(None),
+            // A peer which joined from our checkpoint can't tell which of the events preceding
+            // it we have, so it may gossip us some we already pruned. Skip them.
+            Err(Error::UnknownSelfParent) | Err(Error::UnknownOtherParent)
+                if self.checkpoint_frontiers.contains_key(src_index) =>
+            {
+                return Ok(None)
+            }
+            // Keep it until the missing parents are fetched from the peer. Unpacking already
+            // verified that it is signed by one of our peers.
+            Err(error @ Error::UnknownSelfParent) | Err(error @ Error::UnknownOtherParent) => {
+                self.orphan_events.insert(hash, src_index, packed_event);
+                return Err(error);
+            }
+            Err(error) => return Err(error),
+        };
+
+        let event_creator = event.creator();
+        let event_index = self.add_event(event)?;
+
+        // We have received an event of a peer in the message. The peer can now receive gossips
+        // from us as well.
+        self.peer_list
+            .change_peer_state(event_creator, PeerState::RECV);
+        self.peer_list
+            .record_gossiped_event_by(src_index, event_index);
+
+        #[cfg(feature = "malice-detection")]
+        self.detect_accomplice(event_index)?;
+
+        Ok(Some(event_index))
+    }
+
+    // Inserts the orphans which were waiting for the event `hash`, then the ones waiting for them
+    // in turn.
+    fn add_orphans_of(&mut self, hash: EventHash, pending: &BTreeSet<EventHash>) -> Result<()> {
+        let mut inserted = vec![hash];
+        while let Some(parent) = inserted.pop() {
+            for (child, orphan) in self.orphan_events.take_children_of(&parent) {
+                match self.add_packed_event(orphan.src_index, child, orphan.packed_event, pending) {
+                    Ok(Some(_)) => inserted.push(child),
+                    Ok(None) | Err(Error::UnknownSelfParent) | Err(Error::UnknownOtherParent) => (),
+                    Err(error) => return Err(error),
+                }
+            }
+        }
+        Ok(())
     }
 
     fn unpack(
         &mut self,
         packed_event: PackedEvent<T, S::PublicId>,
     ) -> Result<Option<Event<S::PublicId>>> {
+        if self.graph.is_catching_up_from_checkpoint() {
+            // The events following our checkpoint have parents preceding it we never receive.
+            // Record the ones we don't know as pruned once the signature is verified, i.e. once
+            // unpacking fails only because of them.
+            match Event::unpack(packed_event.clone(), self.event_context()) {
+                Err(Error::UnknownSelfParent) | Err(Error::UnknownOtherParent) => {
+                    for parent in packed_event.parents() {
+                        if self.graph.insert_checkpoint_parent(*parent).is_none() {
+                            return Err(Error::UnknownOtherParent);
+                        }
+                    }
+                }
+                Ok(None) => return Ok(None),
+                _ => (),
+            }
+        }
+
         if let Some(unpacked_event) = Event::unpack(packed_event, self.event_context())? {
+            if let Some(key) = unpacked_event.signing_key {
+                self.peer_list.record_signing_key(
+                    unpacked_event.event.creator(),
+                    key,
+                    unpacked_event.event.index_by_creator(),
+                );
+            }
             if let Some((payload_key, observation_info)) = unpacked_event.observation_for_store {
-                let _ = self
-                    .observations
-                    .entry(payload_key)
-                    .or_insert_with(|| observation_info);
+                if !observation_info.observation.has_valid_weights() {
+                    return Err(Error::InvalidEvent);
+                }
+                self.store_observation(payload_key, observation_info);
             }
             Ok(Some(unpacked_event.event))
         } else {
@@ -619,6 +1756,49 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         }
     }
 
+    fn cast_vote(
+        &mut self,
+        observation: Observation<T, S::PublicId>,
+        consensus_mode: Option<ConsensusMode>,
+        ttl: Option<usize>,
+    ) -> Result<()> {
+        self.confirm_self_state(PeerState::VOTE)?;
+
+        if self.have_voted_for(&observation) {
+            return Err(Error::DuplicateVote);
+        }
+
+        if !observation.has_valid_weights() {
+            return Err(Error::InvalidWeight);
+        }
+
+        if !consensus_mode.map_or(true, ConsensusMode::is_valid) {
+            return Err(Error::InvalidConsensusMode);
+        }
+
+        if let Observation::Rekey {
+            ref old,
+            ref new,
+            ref proof,
+        } = observation
+        {
+            if !is_valid_rekey_proof(old, new, proof) {
+                return Err(Error::SignatureFailure);
+            }
+        }
+
+        self.flush_pending_events()?;
+
+        let self_parent = self.our_last_event_index()?;
+        let expiry = ttl.map(|ttl| self.meta_election.consensus_history().len() + ttl);
+        let vote =
+            Vote::new_with_terms(self.peer_list.our_id(), observation, consensus_mode, expiry);
+        let event = self.new_event_from_vote(self_parent, vote)?;
+
+        let _ = self.add_event(event)?;
+        Ok(())
+    }
+
     fn new_event_from_observation(
         &mut self,
         self_parent: EventIndex,
@@ -628,15 +1808,45 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             Event::new_from_observation(self_parent, observation, self.event_context())?;
 
         if let Some((payload_key, observation_info)) = observation_for_store {
-            let _ = self
-                .observations
-                .entry(payload_key)
-                .or_insert_with(|| observation_info);
+            self.store_observation(payload_key, observation_info);
+        }
+
+        Ok(event)
+    }
+
+    fn new_event_from_vote(
+        &mut self,
+        self_parent: EventIndex,
+        vote: Vote<T, S::PublicId>,
+    ) -> Result<Event<S::PublicId>> {
+        let (event, observation_for_store) =
+            Event::new_from_vote(self_parent, vote, self.event_context())?;
+
+        if let Some((payload_key, observation_info)) = observation_for_store {
+            self.store_observation(payload_key, observation_info);
         }
 
         Ok(event)
     }
 
+    fn store_observation(
+        &mut self,
+        payload_key: ObservationKey,
+        mut observation_info: ObservationInfo<T, S::PublicId>,
+    ) {
+        if let Entry::Vacant(entry) = self.observations.entry(payload_key) {
+            // If the graph has been pruned, this might be an observation which was already
+            // consensused and then discarded. Make sure it doesn't get consensused again.
+            observation_info.consensused = self.graph.start_index() > 0
+                && self
+                    .meta_election
+                    .consensus_history()
+                    .contains(&payload_key);
+
+            let _ = entry.insert(observation_info);
+        }
+    }
+
     fn add_event(&mut self, event: Event<S::PublicId>) -> Result<EventIndex> {
         let our = event.creator() == PeerIndex::OUR;
         if !our {
@@ -654,6 +1864,16 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             return Err(Error::InvalidEvent);
         }
 
+        let history_len = self.meta_election.consensus_history().len();
+        let expired = event
+            .vote_expiry()
+            .map_or(false, |expiry| expiry <= history_len);
+        let has_unexpired_votes = event.payload_key().map_or(false, |key| {
+            self.meta_election
+                .unconsensused_events(Some(key))
+                .next()
+                .is_some()
+        });
         let unconsensused_payload_key = event
             .payload_key()
             .and_then(|key| self.observations.get_mut(key).map(|info| (key, info)))
@@ -663,11 +1883,17 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 }
                 if info.consensused {
                     None
+                } else if expired {
+                    info.expired = !has_unexpired_votes;
+                    None
                 } else {
+                    info.expired = false;
                     Some(*key)
                 }
             });
 
+        self.apply_genesis_weights(&event);
+
         let event_index = self.insert_event(event);
 
         let _ = unconsensused_payload_key.map(|payload_key| {
@@ -687,6 +1913,26 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         Ok(event_index)
     }
 
+    // Sets the voting weights of the genesis group carried by `event`, if it is a genesis event
+    // preceding the first block. This is how the peers joining the section later learn them.
+    fn apply_genesis_weights(&mut self, event: &Event<S::PublicId>) {
+        if !self.meta_election.consensus_history().is_empty() {
+            return;
+        }
+
+        let weights = match self.event_payload(event) {
+            Some(Observation::Genesis { weights, .. }) => weights.clone(),
+            _ => return,
+        };
+        for (peer_id, weight) in weights {
+            let peer_index = match self.peer_list.get_index(&peer_id) {
+                Some(peer_index) if self.meta_election.voters().contains(peer_index) => peer_index,
+                _ => continue,
+            };
+            self.peer_list.set_peer_weight(peer_index, weight);
+        }
+    }
+
     // Create initial event for this node and insert it into the graph. This must be called when
     // this node becomes voter.
     fn add_initial_event(&mut self) {
@@ -737,20 +1983,43 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
 
         self.output_consensus_info(&payload_keys);
 
-        let blocks = self.create_blocks(&payload_keys)?;
-        if !blocks.is_empty() {
-            self.consensused_blocks.push_back(blocks);
+        let election = ElectionInfo::new(
+            self.meta_election.decided_elections(),
+            *self.get_known_event(event_index)?.hash(),
+        );
+        let block_group = self.create_blocks(&payload_keys, election)?;
+        let last_block_hash = block_group.blocks.back().map(Block::hash);
+        if !block_group.is_empty() {
+            if let Some(ref mut listener) = self.consensus_listener {
+                listener.handle_consensus(block_group.blocks.into(), block_group.election);
+            } else {
+                self.consensused_blocks.push_back(block_group);
+            }
         }
 
         self.mark_observations_as_consensused(&payload_keys);
 
+        let voter_weights = self
+            .voters()
+            .iter()
+            .map(|peer_index| (peer_index, self.peer_list.peer_weight(peer_index)))
+            .collect();
         let peer_list_changes = payload_keys
             .iter()
-            .filter_map(|payload_key| self.handle_consensus(event_index, payload_key))
+            .flat_map(|payload_key| self.handle_consensus(event_index, payload_key))
             .collect();
 
-        self.meta_election
-            .new_election(&self.graph, payload_keys, peer_list_changes);
+        let expired_keys = self.meta_election.new_election(
+            &self.graph,
+            payload_keys,
+            peer_list_changes,
+            voter_weights,
+            last_block_hash,
+        );
+        self.mark_observations_as_expired(&expired_keys);
+        self.graph.finish_catching_up_from_checkpoint();
+        self.published_coin_shares.clear();
+        self.counters.tossed_coins.clear();
         self.meta_election
             .initialise_round_hashes(self.peer_list.all_ids());
 
@@ -799,20 +2068,54 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         }
     }
 
+    fn mark_observations_as_expired(&mut self, payload_keys: &[ObservationKey]) {
+        for payload_key in payload_keys {
+            let expired = self
+                .meta_election
+                .unconsensused_events(Some(payload_key))
+                .next()
+                .is_none();
+            if let Some(info) = self.observations.get_mut(payload_key) {
+                info.expired = expired;
+            }
+        }
+    }
+
     /// Handles consensus reached by us.
     fn handle_consensus(
         &mut self,
         event_index: EventIndex,
         payload_key: &ObservationKey,
-    ) -> Option<PeerListChange> {
+    ) -> Vec<PeerListChange> {
         match self
             .observations
             .get(payload_key)
             .map(|info| info.observation.clone())
         {
-            Some(Observation::Add { ref peer_id, .. }) => self.handle_add_peer(peer_id).into(),
-            Some(Observation::Remove { ref peer_id, .. }) => {
-                self.handle_remove_peer(event_index, peer_id)
+            Some(Observation::Add {
+                ref peer_id,
+                weight,
+                ..
+            }) => vec![self.handle_add_or_reweight_peer(peer_id, weight)],
+            Some(Observation::Remove { ref peer_id, .. }) => self
+                .handle_remove_peer(event_index, peer_id)
+                .into_iter()
+                .collect(),
+            Some(Observation::Rekey { old, new, proof }) => {
+                self.handle_rekey_peer(old, new, &proof);
+                vec![]
+            }
+            Some(Observation::Reconfigure { add, remove, .. }) => {
+                // All the changes take effect in the same new election.
+                let removed = remove
+                    .iter()
+                    .filter_map(|peer_id| self.handle_remove_peer(event_index, peer_id))
+                    .collect_vec();
+                add.iter()
+                    .filter(|(peer_id, _)| !remove.contains(peer_id))
+                    .map(|(peer_id, weight)| self.handle_add_or_reweight_peer(peer_id, *weight))
+                    .chain(removed)
+                    .collect()
             }
             Some(Observation::Accusation {
                 ref offender,
@@ -826,16 +2129,31 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 );
 
                 self.handle_remove_peer(event_index, offender)
+                    .into_iter()
+                    .collect()
             }
-            Some(Observation::Genesis(_)) | Some(Observation::OpaquePayload(_)) => None,
+            Some(Observation::Genesis { .. }) | Some(Observation::OpaquePayload(_)) => vec![],
             None => {
                 log_or_panic!("Failed to get observation from hash.");
-                None
+                vec![]
+            }
+        }
+    }
+
+    fn handle_add_or_reweight_peer(
+        &mut self,
+        peer_id: &S::PublicId,
+        weight: u64,
+    ) -> PeerListChange {
+        match self.peer_list.get_index(peer_id) {
+            Some(peer_index) if self.peer_list.peer_state(peer_index).can_vote() => {
+                self.handle_reweight_peer(peer_index, weight)
             }
+            _ => self.handle_add_peer(peer_id, weight),
         }
     }
 
-    fn handle_add_peer(&mut self, peer_id: &S::PublicId) -> PeerListChange {
+    fn handle_add_peer(&mut self, peer_id: &S::PublicId, weight: u64) -> PeerListChange {
         // - If we are already full member of the section, we can start sending gossips to
         //   the new peer from this moment.
         // - If we are the new peer, we must wait for the other members to send gossips to
@@ -872,6 +2190,10 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         } else {
             self.peer_list.add_peer(peer_id.clone(), state)
         };
+        self.peer_list.set_peer_weight(peer_index, weight);
+        if peer_index != PeerIndex::OUR {
+            let _ = self.added_peers.insert(peer_index);
+        }
 
         if peer_index == PeerIndex::OUR && self.peer_list.our_events().next().is_none() {
             self.add_initial_event();
@@ -880,6 +2202,41 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         PeerListChange::Add(peer_index)
     }
 
+    fn handle_reweight_peer(&mut self, peer_index: PeerIndex, weight: u64) -> PeerListChange {
+        self.peer_list.set_peer_weight(peer_index, weight);
+        PeerListChange::Reweight(peer_index)
+    }
+
+    fn handle_rekey_peer(
+        &mut self,
+        old: S::PublicId,
+        new: S::PublicId,
+        proof: &<S::PublicId as PublicId>::Signature,
+    ) {
+        if !is_valid_rekey_proof(&old, &new, proof) {
+            warn!(
+                "{:?} ignoring consensused rekey of {:?} with invalid proof",
+                self.our_pub_id(),
+                old
+            );
+            return;
+        }
+
+        let rekeyed = self
+            .peer_list
+            .get_index(&old)
+            .map(|peer_index| self.peer_list.rekey_peer(peer_index, new.clone()))
+            .unwrap_or(false);
+        if !rekeyed {
+            warn!(
+                "{:?} failed to rekey {:?} to {:?}",
+                self.our_pub_id(),
+                old,
+                new
+            );
+        }
+    }
+
     fn handle_remove_peer(
         &mut self,
         event_index: EventIndex,
@@ -909,10 +2266,18 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
 
         self.set_interesting_content(&mut builder);
         self.set_observer(&mut builder);
-        self.set_meta_votes(&mut builder)?;
+        let mut tossed_coins = BTreeSet::new();
+        self.set_meta_votes(&mut builder, &mut tossed_coins)?;
 
         self.meta_election.add_meta_event(builder);
 
+        // The same coin is tossed again every time the meta-votes of a later event are computed.
+        for round_hash in tossed_coins {
+            if self.counters.tossed_coins.insert(round_hash) {
+                self.counters.coin_tosses += 1;
+            }
+        }
+
         Ok(())
     }
 
@@ -948,54 +2313,97 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     }
 
     // Returns true if enough of `valid_voters` have voted for the indicated payload from the
-    // perspective of `builder.event()`.
+    // perspective of `builder.event()`. During a joint-consensus transition, enough of the previous
+    // voters must have voted for it too.
     fn is_interesting_payload(
         &self,
         builder: &MetaEventBuilder<S::PublicId>,
         peers_that_can_vote: &PeerIndexSet,
         payload_key: &ObservationKey,
     ) -> bool {
-        let num_peers_that_did_vote = || {
-            self.num_creators_of_ancestors_carrying_payload(
+        let current_weight = |peer_index| u128::from(self.peer_list.peer_weight(peer_index));
+        self.is_interesting_payload_for_voters(
+            builder,
+            peers_that_can_vote,
+            &current_weight,
+            payload_key,
+        ) && self
+            .meta_election
+            .previous_voters()
+            .map(|previous_voters| {
+                self.is_interesting_payload_for_voters(
+                    builder,
+                    &previous_voters.keys().collect(),
+                    &|peer_index| self.previous_weight(peer_index),
+                    payload_key,
+                )
+            })
+            .unwrap_or(true)
+    }
+
+    // Same as `is_interesting_payload`, counting `peers_that_can_vote` with the weights given by
+    // `weight`.
+    fn is_interesting_payload_for_voters<F: Fn(PeerIndex) -> u128>(
+        &self,
+        builder: &MetaEventBuilder<S::PublicId>,
+        peers_that_can_vote: &PeerIndexSet,
+        weight: &F,
+        payload_key: &ObservationKey,
+    ) -> bool {
+        let weight_of_peers_that_did_vote = || {
+            self.weight_of_creators_of_ancestors_carrying_payload(
                 peers_that_can_vote,
+                weight,
                 builder.event(),
                 payload_key,
             )
         };
+        let total_weight = peers_that_can_vote.iter().map(weight).sum();
 
         match payload_key.consensus_mode() {
             ConsensusMode::Single => {
-                let num_ancestor_peers =
-                    self.num_creators_of_ancestors(peers_that_can_vote, &*builder.event());
-                is_more_than_two_thirds(num_ancestor_peers, peers_that_can_vote.len())
-                    && num_peers_that_did_vote() > 0
+                let weight_of_ancestor_peers = Self::weight_of_creators_of_ancestors(
+                    peers_that_can_vote,
+                    weight,
+                    &*builder.event(),
+                );
+                is_more_than_two_thirds_of_weight(weight_of_ancestor_peers, total_weight)
+                    && weight_of_peers_that_did_vote() > 0
             }
             ConsensusMode::Supermajority => {
-                is_more_than_two_thirds(num_peers_that_did_vote(), peers_that_can_vote.len())
+                is_more_than_two_thirds_of_weight(weight_of_peers_that_did_vote(), total_weight)
             }
+            ConsensusMode::Threshold(percent) => is_more_than_percent_of_weight(
+                weight_of_peers_that_did_vote(),
+                percent,
+                total_weight,
+            ),
         }
     }
 
-    // Number of unique peers that created at least one ancestor of the given event.
-    fn num_creators_of_ancestors(
-        &self,
+    // Total weight of the unique peers that created at least one ancestor of the given event.
+    fn weight_of_creators_of_ancestors<F: Fn(PeerIndex) -> u128>(
         peers_that_can_vote: &PeerIndexSet,
+        weight: &F,
         event: &Event<S::PublicId>,
-    ) -> usize {
+    ) -> u128 {
         event
             .last_ancestors()
-            .filter(|(peer_index, _)| peers_that_can_vote.contains(*peer_index))
-            .count()
+            .map(|(peer_index, _)| peer_index)
+            .filter(|peer_index| peers_that_can_vote.contains(*peer_index))
+            .map(weight)
+            .sum()
     }
 
-    // Number of unique peers that created at least one ancestor of the given event that carries the
-    // given payload.
-    fn num_creators_of_ancestors_carrying_payload(
+    // Total weight of the unique peers that created at least one ancestor of the given event that
+    // carries the given payload.
+    fn weight_of_creators_of_ancestors_carrying_payload<F: Fn(PeerIndex) -> u128>(
         &self,
         peers_that_can_vote: &PeerIndexSet,
+        weight: &F,
         event: IndexedEventRef<S::PublicId>,
         payload_key: &ObservationKey,
-    ) -> usize {
+    ) -> u128 {
         let unconsensused_events = self.unconsensused_events(Some(payload_key)).collect_vec();
 
         peers_that_can_vote
@@ -1005,7 +2413,8 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                     that_event.creator() == *peer_index && event.is_descendant_of(*that_event)
                 })
             })
-            .count()
+            .map(weight)
+            .sum()
     }
 
     fn set_observer(&self, builder: &mut MetaEventBuilder<S::PublicId>) {
@@ -1017,7 +2426,6 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             return;
         }
 
-        let voter_count = self.voter_count();
         let observees: PeerIndexSet = self
             .meta_election
             .interesting_events()
@@ -1032,7 +2440,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             })
             .collect();
 
-        if is_more_than_two_thirds(observees.len(), voter_count) {
+        if self.is_supermajority(&observees) {
             builder.set_observer(Observer::This(observees));
         } else {
             builder.set_observer(Observer::None);
@@ -1047,7 +2455,13 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             .unwrap_or(false)
     }
 
-    fn set_meta_votes(&self, builder: &mut MetaEventBuilder<S::PublicId>) -> Result<()> {
+    // Sets the meta-votes of the event, and adds the round hashes of the coins tossed for them to
+    // `tossed_coins`.
+    fn set_meta_votes(
+        &self,
+        builder: &mut MetaEventBuilder<S::PublicId>,
+        tossed_coins: &mut BTreeSet<Hash>,
+    ) -> Result<()> {
         let parent_meta_votes = self
             .graph
             .self_sync_parent(builder.event())
@@ -1070,6 +2484,11 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
 
         let ancestors_meta_votes =
             self.other_voting_ancestors_meta_votes(&voters, &builder.event());
+        let weights = VoteWeights {
+            own: self.voter_weight(builder.event().creator()),
+            total: self.total_weight(voters),
+            previous_total: self.previous_total_weight(),
+        };
 
         if let Some(parent_meta_votes) = parent_meta_votes {
             // Parent has meta votes: Derive this event's meta votes from them.
@@ -1092,7 +2511,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 .into_iter()
                 .map(|(peer_index, parent_votes)| {
                     let temp_votes =
-                        MetaVote::next_temp(parent_votes, &other_votes[&peer_index], voters.len());
+                        MetaVote::next_temp(parent_votes, &other_votes[&peer_index], weights);
 
                     (peer_index, temp_votes)
                 })
@@ -1104,12 +2523,13 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             };
 
             for (peer_index, temp_votes) in &context.temp_votes {
-                let coin_tosses = self.toss_coins(&voters, peer_index, temp_votes, &context)?;
+                let coin_tosses =
+                    self.toss_coins(&voters, peer_index, temp_votes, &context, tossed_coins)?;
                 let final_meta_votes = MetaVote::next_final(
                     temp_votes,
                     &other_votes[&peer_index],
                     &coin_tosses,
-                    voters.len(),
+                    weights,
                 );
 
                 builder.add_meta_votes(peer_index, final_meta_votes);
@@ -1121,7 +2541,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                     let other_votes = Self::peer_meta_votes(&ancestors_meta_votes, peer_index);
                     let initial_estimate = builder.has_observee(peer_index);
 
-                    MetaVote::new_for_observer(initial_estimate, &other_votes, voters.len())
+                    MetaVote::new_for_observer(initial_estimate, &other_votes, weights)
                 };
 
                 builder.add_meta_votes(peer_index, new_meta_votes);
@@ -1143,11 +2563,14 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         peer_index: PeerIndex,
         temp_votes: &[MetaVote],
         context: &MetaVoteContext<S::PublicId>,
+        tossed_coins: &mut BTreeSet<Hash>,
     ) -> Result<BTreeMap<usize, bool>> {
         let mut coin_tosses = BTreeMap::new();
         for temp_vote in temp_votes {
             // Safe ourselves some effort if the meta vote is already decided
-            if let Some(coin) = self.toss_coin(voters, peer_index, temp_vote, context)? {
+            if let Some(coin) =
+                self.toss_coin(voters, peer_index, temp_vote, context, tossed_coins)?
+            {
                 let _ = coin_tosses.insert(temp_vote.round, coin);
             }
         }
@@ -1160,6 +2583,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         peer_index: PeerIndex,
         temp_vote: &MetaVote,
         context: &MetaVoteContext<S::PublicId>,
+        tossed_coins: &mut BTreeSet<Hash>,
     ) -> Result<Option<bool>> {
         // Get the round hash.
         let round = if temp_vote.has_empty_estimates() {
@@ -1190,6 +2614,27 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             return Err(Error::Logic);
         };
 
+        let leader_coin = || self.leader_coin(voters, peer_index, round, round_hash, context);
+        let shares = || self.coin_shares(voters, round_hash, context);
+        let coin = self.common_coin.toss(
+            round_hash.as_bytes(),
+            &CoinContext::new(&leader_coin, &shares),
+        );
+
+        if coin.is_some() {
+            let _ = tossed_coins.insert(*round_hash);
+        }
+        Ok(coin)
+    }
+
+    fn leader_coin(
+        &self,
+        voters: &PeerIndexSet,
+        peer_index: PeerIndex,
+        round: usize,
+        round_hash: &Hash,
+        context: &MetaVoteContext<S::PublicId>,
+    ) -> Option<bool> {
         // Get the gradient of leadership.
         let mut peer_id_hashes: Vec<_> = self
             .peer_list
@@ -1204,7 +2649,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             if let Some(coin) =
                 self.coin_value(creator, index_by_creator, peer_index, round, context)
             {
-                return Ok(Some(coin));
+                return Some(coin);
             }
         }
 
@@ -1215,13 +2660,109 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                     if let Some(coin) =
                         self.coin_value(*creator, index_by_creator, peer_index, round, context)
                     {
-                        return Ok(Some(coin));
+                        return Some(coin);
                     }
                 }
             }
         }
 
-        Ok(None)
+        None
+    }
+
+    // Collects the shares of the coin identified by `round_hash` carried by the ancestors of the
+    // current event, taking the first share by each voter.
+    fn coin_shares(
+        &self,
+        voters: &PeerIndexSet,
+        round_hash: &Hash,
+        context: &MetaVoteContext<S::PublicId>,
+    ) -> BTreeMap<S::PublicId, Vec<u8>> {
+        let mut shares = BTreeMap::new();
+        for creator in voters {
+            let last_index_by_creator =
+                if let Some(index) = context.event.non_fork_last_ancestor_by(creator) {
+                    index
+                } else {
+                    continue;
+                };
+            let peer_id = if let Some(peer) = self.peer_list.get(creator) {
+                peer.id()
+            } else {
+                continue;
+            };
+
+            let share = (0..=last_index_by_creator)
+                .filter_map(|index_by_creator| {
+                    self.peer_list
+                        .events_by_index(creator, index_by_creator)
+                        .next()
+                })
+                .filter(|event_index| {
+                    event_index.topological_index()
+                        >= self.meta_election.continue_consensus_start_index()
+                })
+                .filter_map(|event_index| self.graph.get(event_index))
+                .filter_map(|event| event.inner().coin_share(round_hash))
+                .next();
+            if let Some(share) = share {
+                let _ = shares.insert(peer_id.clone(), share.to_vec());
+            }
+        }
+        shares
+    }
+
+    // Publishes our shares of the coins our last event might need to toss.
+    fn create_coin_share_events(&mut self) -> Result<()> {
+        let last_event_index = if let Some(index) = self.peer_list.last_event(PeerIndex::OUR) {
+            index
+        } else {
+            return Ok(());
+        };
+
+        let meta_event = if let Some(meta_event) = self.meta_election.meta_event(last_event_index) {
+            meta_event
+        } else {
+            return Ok(());
+        };
+
+        let mut round_hashes = vec![];
+        for (peer_index, meta_votes) in &meta_event.meta_votes {
+            let hashes = if let Some(hashes) = self.meta_election.round_hashes(peer_index) {
+                hashes
+            } else {
+                continue;
+            };
+            round_hashes.extend(
+                meta_votes
+                    .iter()
+                    .filter(|meta_vote| {
+                        meta_vote.step == Step::GenuineFlip && meta_vote.decision().is_none()
+                    })
+                    .filter_map(|meta_vote| hashes.get(meta_vote.round))
+                    .map(|round_hash| *round_hash.value()),
+            );
+        }
+
+        for round_hash in round_hashes {
+            if !self.published_coin_shares.insert(round_hash) {
+                continue;
+            }
+            let share = if let Some(share) = self.common_coin.create_share(round_hash.as_bytes()) {
+                share
+            } else {
+                continue;
+            };
+
+            let event = Event::new_from_coin_share(
+                self.our_last_event_index()?,
+                round_hash,
+                share,
+                self.event_context(),
+            )?;
+            let _ = self.add_event(event)?;
+        }
+
+        Ok(())
     }
 
     // Computes the coin toss value for the given peer from the first event by `creator` that has a
@@ -1275,7 +2816,9 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         let responsiveness_threshold = self.responsiveness_threshold();
 
         loop {
-            if let Some(event) = event_index.and_then(|index| self.get_known_event(index).ok()) {
+            // A pruned self-parent or one preceding our checkpoint ends the walk, as we don't have
+            // it.
+            if let Some(event) = event_index.and_then(|index| self.graph.get(index)) {
                 if event.is_response() {
                     response_count += 1;
                     if response_count == responsiveness_threshold {
@@ -1317,12 +2860,13 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         }
     }
 
-    // Returns all the meta votes from the event's voting ancestors except the event's creator.
+    // Returns all the meta votes from the event's voting ancestors except the event's creator,
+    // along with the weights of their creators.
     fn other_voting_ancestors_meta_votes(
         &self,
         voters: &PeerIndexSet,
         event: &Event<S::PublicId>,
-    ) -> Vec<&PeerIndexMap<Vec<MetaVote>>> {
+    ) -> Vec<(&PeerIndexMap<Vec<MetaVote>>, VoterWeight)> {
         voters
             .iter()
             .filter(|voter_index| *voter_index != event.creator())
@@ -1336,19 +2880,23 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                             .next()?;
                         self.meta_election.populated_meta_votes(event_index)
                     })
+                    .map(|meta_votes| (meta_votes, self.voter_weight(creator)))
             })
             .collect()
     }
 
-    // Collect the vectors of meta votes for the peer
+    // Collect the vectors of meta votes for the peer, along with the weights of their voters.
     fn peer_meta_votes<'a>(
-        meta_votes_maps: &'a [&PeerIndexMap<Vec<MetaVote>>],
+        meta_votes_maps: &'a [(&PeerIndexMap<Vec<MetaVote>>, VoterWeight)],
         peer_index: PeerIndex,
-    ) -> Vec<&'a [MetaVote]> {
+    ) -> Vec<(&'a [MetaVote], VoterWeight)> {
         meta_votes_maps
             .iter()
-            .filter_map(|meta_votes| meta_votes.get(peer_index))
-            .map(Vec::as_slice)
+            .filter_map(|(meta_votes, weight)| {
+                meta_votes
+                    .get(peer_index)
+                    .map(|meta_votes| (meta_votes.as_slice(), *weight))
+            })
             .collect()
     }
 
@@ -1362,6 +2910,69 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         self.meta_election.voters().len()
     }
 
+    // Total weight of the voters for the given meta-election.
+    fn voters_weight(&self) -> u128 {
+        self.total_weight(self.voters())
+    }
+
+    // Returns whether `peers` hold more than two thirds of the total weight of the voters. During a
+    // joint-consensus transition, they must also hold more than two thirds of the total weight of
+    // the previous voters.
+    fn is_supermajority(&self, peers: &PeerIndexSet) -> bool {
+        is_more_than_two_thirds_of_weight(self.total_weight(peers), self.voters_weight())
+            && self
+                .previous_total_weight()
+                .map(|previous_total_weight| {
+                    is_more_than_two_thirds_of_weight(
+                        peers
+                            .iter()
+                            .map(|peer_index| self.previous_weight(peer_index))
+                            .sum(),
+                        previous_total_weight,
+                    )
+                })
+                .unwrap_or(true)
+    }
+
+    // Weight the meta votes of `peer_index` count with among the voters and, during a
+    // joint-consensus transition, among the previous voters.
+    fn voter_weight(&self, peer_index: PeerIndex) -> VoterWeight {
+        VoterWeight {
+            current: u128::from(self.peer_list.peer_weight(peer_index)),
+            previous: self.previous_weight(peer_index),
+        }
+    }
+
+    // Weight `peer_index` held among the previous voters, or zero if it isn't one of them or the
+    // meta-election isn't a joint-consensus transition.
+    fn previous_weight(&self, peer_index: PeerIndex) -> u128 {
+        self.meta_election
+            .previous_voters()
+            .and_then(|previous_voters| previous_voters.get(peer_index))
+            .map_or(0, |weight| u128::from(*weight))
+    }
+
+    // Total weight of the previous voters, if the meta-election is a joint-consensus transition.
+    fn previous_total_weight(&self) -> Option<u128> {
+        self.meta_election.previous_voters().map(|previous_voters| {
+            previous_voters
+                .iter()
+                .map(|(_, weight)| u128::from(*weight))
+                .sum()
+        })
+    }
+
+    // Total weight of the given peers.
+    fn total_weight<I>(&self, peers: I) -> u128
+    where
+        I: IntoIterator<Item = PeerIndex>,
+    {
+        peers
+            .into_iter()
+            .map(|peer_index| u128::from(self.peer_list.peer_weight(peer_index)))
+            .sum()
+    }
+
     fn unconsensused_events(
         &self,
         filter_key: Option<&ObservationKey>,
@@ -1432,7 +3043,16 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             all_payloads_lookup.extend(new_payloads.iter());
         }
 
+        // The sort is stable, so the payloads with the same priority keep their order.
         all_payloads_in_order
+            .sort_by_key(|payload_key| cmp::Reverse(self.payload_priority(payload_key)));
+        all_payloads_in_order
+    }
+
+    fn payload_priority(&self, payload_key: &ObservationKey) -> i64 {
+        self.observations
+            .get(payload_key)
+            .map_or(0, |info| info.observation.priority())
     }
 
     // Iterate the payload iterators skipping processed payloads and accumulate this batch of payload count.
@@ -1472,42 +3092,56 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             .collect_vec()
     }
 
-    fn create_blocks(&self, payload_keys: &[ObservationKey]) -> Result<BlockGroup<T, S::PublicId>> {
+    fn create_blocks(
+        &self,
+        payload_keys: &[ObservationKey],
+        election: ElectionInfo,
+    ) -> Result<BlockGroup<T, S::PublicId>> {
         let voters = self.voters();
-        let blocks: Result<VecDeque<_>> = payload_keys
-            .iter()
-            .map(|payload_key| {
-                let votes = self
-                    .unconsensused_events(Some(payload_key))
-                    .map(|event| event.inner())
-                    .filter(|event| voters.contains(event.creator()))
-                    .filter_map(|event| {
-                        let (vote, key) = event.vote_and_payload_key(&self.observations)?;
-                        let creator_id = self.peer_list.get(event.creator()).map(Peer::id)?;
-                        Some((key, vote, creator_id))
-                    })
-                    .map(|(_, vote, creator_id)| (creator_id.clone(), vote.clone()))
-                    .collect();
+        let first_sequence_number = self.meta_election.consensus_history().len();
+        // A payload consensused without any vote by a voter has no block, so the next block is
+        // chained to the last one actually created.
+        let mut previous_hash = self.meta_election.last_block_hash().cloned();
+        let mut blocks = VecDeque::new();
+        for (position, payload_key) in payload_keys.iter().enumerate() {
+            let votes = self
+                .unconsensused_events(Some(payload_key))
+                .map(|event| event.inner())
+                .filter(|event| voters.contains(event.creator()))
+                .filter_map(|event| {
+                    let (vote, key) = event.vote_and_payload_key(&self.observations)?;
+                    let creator = self.peer_list.get(event.creator())?;
+                    // The vote might have been signed before the creator rotated its key.
+                    let creator_id = creator
+                        .signer(vote.signature(), &vote.signed_data())
+                        .unwrap_or_else(|| creator.current_id());
+                    Some((key, vote, creator_id))
+                })
+                .map(|(_, vote, creator_id)| (creator_id.clone(), vote.clone()))
+                .collect();
 
-                Block::new(&votes)
-            })
-            .filter(|block| match block {
-                Err(Error::MissingVotes) => false,
-                Err(_) | Ok(_) => true,
-            })
-            .collect();
+            let block = match Block::new(&votes) {
+                Ok(block) => block
+                    .in_chain(first_sequence_number + position, previous_hash)
+                    .with_consensus_mode(payload_key.consensus_mode()),
+                Err(Error::MissingVotes) => continue,
+                Err(error) => return Err(error),
+            };
+            previous_hash = Some(block.hash());
+            blocks.push_back(block);
+        }
 
-        Ok(BlockGroup(blocks?))
+        Ok(BlockGroup { blocks, election })
     }
 
-    // Returns the number of peers that created events which are seen by event X (descendant) and
-    // see event Y (ancestor). This means number of peers through which there is a directed path
-    // between x and y, excluding peers contains fork.
-    fn num_peers_created_events_seen_by_x_that_can_see_y(
+    // Returns the peers that created events which are seen by event X (descendant) and see event Y
+    // (ancestor). These are the peers through which there is a directed path between x and y,
+    // excluding peers contains fork.
+    fn peers_created_events_seen_by_x_that_can_see_y(
         &self,
         x: &Event<S::PublicId>,
         y: &Event<S::PublicId>,
-    ) -> usize {
+    ) -> PeerIndexSet {
         x.last_ancestors()
             .filter(|(peer_index, event_index)| {
                 for event_idx in self.peer_list.events_by_index(*peer_index, *event_index) {
@@ -1519,7 +3153,8 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 }
                 false
             })
-            .count()
+            .map(|(peer_index, _)| peer_index)
+            .collect()
     }
 
     // Returns whether event X can strongly see the event Y during the evaluation of the given
@@ -1529,9 +3164,8 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         A: AsRef<Event<S::PublicId>>,
         B: AsRef<Event<S::PublicId>>,
     {
-        is_more_than_two_thirds(
-            self.num_peers_created_events_seen_by_x_that_can_see_y(x.as_ref(), y.as_ref()),
-            self.voter_count(),
+        self.is_supermajority(
+            &self.peers_created_events_seen_by_x_that_can_see_y(x.as_ref(), y.as_ref()),
         )
     }
 
@@ -1583,22 +3217,105 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         };
 
         // Events to include in the result. Initially start with including everything...
+        let start_index = self.graph.start_index();
         let mut inclusion_list = vec![true; self.graph.len()];
 
         // ...then exclude events that are ancestors of `last_event`, because the peer already has
         // them.
         for event in self.graph.ancestors(last_event) {
-            inclusion_list[event.topological_index()] = false;
+            inclusion_list[event.topological_index() - start_index] = false;
         }
 
         Ok(self
             .graph
             .iter()
-            .filter(|event| inclusion_list[event.topological_index()])
+            .filter(|event| inclusion_list[event.topological_index() - start_index])
             .map(|event| event.inner())
             .collect())
     }
 
+    // Estimates the number of the events we know of which are not ancestors of the last event of
+    // `peer_index` we know of, by comparing the last event of each creator we know of with its last
+    // ancestor by that creator. Forks are ignored.
+    fn estimate_events_missing_from(&self, peer_index: PeerIndex) -> usize {
+        let last_event = self
+            .peer_list
+            .last_event(peer_index)
+            .and_then(|event_index| self.graph.get(event_index));
+        self.peer_list
+            .iter()
+            .filter_map(|(creator, _)| {
+                let known = self
+                    .peer_list
+                    .last_event(creator)
+                    .and_then(|event_index| self.graph.get(event_index))?
+                    .index_by_creator()
+                    + 1;
+                let known_by_peer = last_event
+                    .and_then(|event| event.last_ancestor_by(creator))
+                    .map_or(0, |index_by_creator| index_by_creator + 1);
+                Some(known.saturating_sub(known_by_peer))
+            })
+            .sum()
+    }
+
+    // Returns the events to gossip to a peer we haven't received any gossip from yet: only the
+    // ones following our checkpoint if the peer is expected to join from it, otherwise all of them.
+    fn events_to_gossip_to_new_peer(&self, peer_index: PeerIndex) -> Vec<&Event<S::PublicId>> {
+        let frontier = self
+            .checkpoint_frontiers
+            .get(peer_index)
+            .and_then(|event_index| self.graph.get(*event_index));
+        let frontier = if let Some(frontier) = frontier {
+            frontier
+        } else {
+            return self.graph.iter().map(|event| event.inner()).collect();
+        };
+
+        let preceding: BTreeSet<_> = self
+            .graph
+            .ancestors(frontier)
+            .map(|event| event.event_index())
+            .collect();
+        self.graph
+            .iter()
+            .filter(|event| !preceding.contains(&event.event_index()))
+            .map(|event| event.inner())
+            .collect()
+    }
+
+    // Returns the hashes of the events preceding `frontier` which the events following it can
+    // have as parents: the last ones of each creator, and the parents of the following events we
+    // already have.
+    fn checkpoint_frontier_hashes(&self, frontier: EventIndex) -> BTreeSet<EventHash> {
+        let frontier = if let Some(frontier) = self.graph.get(frontier) {
+            frontier
+        } else {
+            return BTreeSet::new();
+        };
+
+        let preceding: BTreeSet<_> = self
+            .graph
+            .ancestors(frontier)
+            .map(|event| event.event_index())
+            .collect();
+        let last_events = frontier
+            .last_ancestors()
+            .flat_map(|(peer_index, index_by_creator)| {
+                self.peer_list.events_by_index(peer_index, index_by_creator)
+            });
+        let parents_of_following = self
+            .graph
+            .iter()
+            .filter(|event| !preceding.contains(&event.event_index()))
+            .flat_map(|event| event.self_parent().into_iter().chain(event.other_parent()))
+            .filter(|event_index| preceding.contains(event_index));
+        last_events
+            .chain(parents_of_following)
+            .filter_map(|event_index| self.graph.get_hash(event_index))
+            .collect()
+    }
+
     // Get the responsiveness threshold based on the current number of peers.
     fn responsiveness_threshold(&self) -> usize {
         (self.voter_count() as f64).log2().ceil() as usize
@@ -1643,7 +3360,6 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         }
     }
 
-    #[cfg(any(all(test, feature = "mock"), feature = "malice-detection"))]
     fn event_payload<'a>(
         &'a self,
         event: &Event<S::PublicId>,
@@ -1721,6 +3437,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             )?;
 
             let _ = self.add_event(event)?;
+            self.counters.accusations_raised += 1;
             Ok(None)
         } else {
             Ok(Some(malice))
@@ -1762,8 +3479,12 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
 
     // Detect if the event carries an `Observation::Genesis` that doesn't match what we'd expect.
     fn detect_incorrect_genesis(&mut self, event: &Event<S::PublicId>) -> Result<()> {
-        if let Some(Observation::Genesis(ref group)) = self.event_payload(event) {
-            if self.genesis_group() == group.iter().collect() {
+        if let Some(Observation::Genesis { group, weights }) = self.event_payload(event) {
+            if self.genesis_group() == group.iter().collect()
+                && self
+                    .genesis_weights()
+                    .map_or(true, |genesis| genesis == weights)
+            {
                 return Ok(());
             }
         } else {
@@ -1840,7 +3561,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 return;
             };
 
-            let genesis_group = if let Observation::Genesis(ref group) = *payload {
+            let genesis_group = if let Observation::Genesis { ref group, .. } = *payload {
                 group
             } else {
                 return;
@@ -1872,7 +3593,17 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             return;
         }
 
-        if let Some(&Observation::Genesis(_)) = self.event_payload(event) {
+        // The first events we have of a creator follow an event preceding our checkpoint rather
+        // than its initial event, and aren't expected to carry the genesis.
+        if !self
+            .graph
+            .self_parent(event)
+            .map_or(false, |self_parent| self_parent.is_initial())
+        {
+            return;
+        }
+
+        if let Some(&Observation::Genesis { .. }) = self.event_payload(event) {
             return;
         }
 
@@ -1968,8 +3699,10 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         }
         let mut invalid_accusations = vec![];
         let mut self_parent_index = event.self_parent();
+        // The walk stops at a pruned self-parent or one preceding our checkpoint, as we don't have
+        // it.
         while let Some(self_parent) =
-            self_parent_index.and_then(|event_index| self.get_known_event(event_index).ok())
+            self_parent_index.and_then(|event_index| self.graph.get(event_index))
         {
             match self.event_payload(&self_parent) {
                 Some(&Observation::Accusation {
@@ -2195,18 +3928,27 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     }
 
     fn genesis_group(&self) -> BTreeSet<&S::PublicId> {
+        match self.first_genesis_observation() {
+            Some(Observation::Genesis { group, .. }) => group.iter().collect(),
+            _ => self.peer_list.voters().map(|(_, peer)| peer.id()).collect(),
+        }
+    }
+
+    fn genesis_weights(&self) -> Option<&BTreeMap<S::PublicId, u64>> {
+        match self.first_genesis_observation() {
+            Some(Observation::Genesis { weights, .. }) => Some(weights),
+            _ => None,
+        }
+    }
+
+    fn first_genesis_observation(&self) -> Option<&Observation<T, S::PublicId>> {
         self.graph
             .iter()
-            .filter_map(|event| {
-                let observation = self.event_payload(&*event)?;
-                if let Observation::Genesis(ref gen) = *observation {
-                    Some(gen.iter().collect())
-                } else {
-                    None
-                }
+            .filter_map(|event| self.event_payload(&*event))
+            .find(|observation| match observation {
+                Observation::Genesis { .. } => true,
+                _ => false,
             })
-            .next()
-            .unwrap_or_else(|| self.peer_list.voters().map(|(_, peer)| peer.id()).collect())
     }
 }
 
@@ -2246,6 +3988,213 @@ enum PostProcessAction {
 #[cfg(feature = "malice-detection")]
 type Accusations<T, P> = Vec<(PeerIndex, Malice<T, P>)>;
 
+// State of the gossip exchange with a single peer which was split over several messages because
+// of `Parsec::max_gossip_size`.
+struct ChunkedSync<T: NetworkEvent, P: PublicId> {
+    // Parts of our request which haven't been sent yet.
+    request_chunks: VecDeque<Vec<PackedEvent<T, P>>>,
+    // Number of parts of our request the peer hasn't acknowledged yet.
+    unacknowledged_chunks: usize,
+    // Parts of our response which haven't been sent yet.
+    response_chunks: VecDeque<Vec<PackedEvent<T, P>>>,
+    // Whether the peer has more parts of its response for us.
+    awaiting_response: bool,
+}
+
+impl<T: NetworkEvent, P: PublicId> ChunkedSync<T, P> {
+    fn new() -> Self {
+        Self {
+            request_chunks: VecDeque::new(),
+            unacknowledged_chunks: 0,
+            response_chunks: VecDeque::new(),
+            awaiting_response: false,
+        }
+    }
+
+    fn is_unfinished(&self) -> bool {
+        !self.request_chunks.is_empty() || self.awaiting_response
+    }
+}
+
+// Maximum number of events kept in the orphan pool. Once it's full, the oldest one is dropped and
+// has to be fetched again.
+pub(crate) const MAX_ORPHAN_EVENTS: usize = 1024;
+
+// Maximum number of events received from a single peer kept in the orphan pool, so that one peer
+// can't take the whole pool. Once reached, the oldest event from that peer is dropped.
+const MAX_ORPHAN_EVENTS_PER_PEER: usize = MAX_ORPHAN_EVENTS / 4;
+
+// Maximum number of events a `Fetch` can ask for: the parents of all the orphan events.
+pub(crate) const MAX_FETCHED_HASHES: usize = 2 * MAX_ORPHAN_EVENTS;
+
+// An event received before some of its parents.
+#[serde(bound = "")]
+#[derive(Serialize, Deserialize)]
+struct OrphanEvent<T: NetworkEvent, P: PublicId> {
+    // The peer which sent it to us.
+    src_index: PeerIndex,
+    packed_event: PackedEvent<T, P>,
+    // Order in which it was buffered, so the oldest is dropped first.
+    arrival: u64,
+}
+
+// Bounded buffer of the events received before some of their parents, by their hashes.
+#[serde(bound = "")]
+#[derive(Serialize, Deserialize)]
+struct OrphanPool<T: NetworkEvent, P: PublicId> {
+    events: BTreeMap<EventHash, OrphanEvent<T, P>>,
+    // Hashes of the buffered events by the hashes of their parents.
+    children: BTreeMap<EventHash, BTreeSet<EventHash>>,
+    // Hashes of the buffered events by their arrival.
+    arrivals: BTreeMap<u64, EventHash>,
+    // Hashes of the buffered events by the peer which sent them and their arrival.
+    arrivals_by_src: PeerIndexMap<BTreeMap<u64, EventHash>>,
+    next_arrival: u64,
+}
+
+impl<T: NetworkEvent, P: PublicId> OrphanPool<T, P> {
+    fn new() -> Self {
+        Self {
+            events: BTreeMap::new(),
+            children: BTreeMap::new(),
+            arrivals: BTreeMap::new(),
+            arrivals_by_src: PeerIndexMap::new(),
+            next_arrival: 0,
+        }
+    }
+
+    fn contains(&self, hash: &EventHash) -> bool {
+        self.events.contains_key(hash)
+    }
+
+    fn insert(&mut self, hash: EventHash, src_index: PeerIndex, packed_event: PackedEvent<T, P>) {
+        let _ = self.remove(&hash);
+        let oldest_from_src = self
+            .arrivals_by_src
+            .get(src_index)
+            .filter(|arrivals| arrivals.len() >= MAX_ORPHAN_EVENTS_PER_PEER)
+            .and_then(|arrivals| arrivals.values().next().cloned());
+        if let Some(oldest) = oldest_from_src {
+            let _ = self.remove(&oldest);
+        } else if self.events.len() >= MAX_ORPHAN_EVENTS {
+            let oldest = self.arrivals.values().next().cloned();
+            if let Some(oldest) = oldest {
+                let _ = self.remove(&oldest);
+            }
+        }
+
+        let arrival = self.next_arrival;
+        self.next_arrival += 1;
+        for parent in packed_event.parents() {
+            let _ = self
+                .children
+                .entry(*parent)
+                .or_insert_with(BTreeSet::new)
+                .insert(hash);
+        }
+        let _ = self.arrivals.insert(arrival, hash);
+        let _ = self
+            .arrivals_by_src
+            .entry(src_index)
+            .or_insert_with(BTreeMap::new)
+            .insert(arrival, hash);
+        let _ = self.events.insert(
+            hash,
+            OrphanEvent {
+                src_index,
+                packed_event,
+                arrival,
+            },
+        );
+    }
+
+    fn remove(&mut self, hash: &EventHash) -> Option<OrphanEvent<T, P>> {
+        let orphan = self.events.remove(hash)?;
+        let _ = self.arrivals.remove(&orphan.arrival);
+        if let Some(arrivals) = self.arrivals_by_src.get_mut(orphan.src_index) {
+            let _ = arrivals.remove(&orphan.arrival);
+        }
+        for parent in orphan.packed_event.parents() {
+            if let Some(children) = self.children.get_mut(parent) {
+                let _ = children.remove(hash);
+                if children.is_empty() {
+                    let _ = self.children.remove(parent);
+                }
+            }
+        }
+        Some(orphan)
+    }
+
+    // Removes and returns the events having `parent` as one of their parents, oldest first.
+    fn take_children_of(&mut self, parent: &EventHash) -> Vec<(EventHash, OrphanEvent<T, P>)> {
+        let children = self.children.remove(parent).unwrap_or_default();
+        let mut orphans: Vec<_> = children
+            .into_iter()
+            .filter_map(|hash| self.remove(&hash).map(|orphan| (hash, orphan)))
+            .collect();
+        orphans.sort_by_key(|(_, orphan)| orphan.arrival);
+        orphans
+    }
+
+    // Returns the parents of the events received from `src_index` which are not buffered
+    // themselves.
+    fn missing_parents(&self, src_index: PeerIndex) -> BTreeSet<EventHash> {
+        self.arrivals_by_src
+            .get(src_index)
+            .into_iter()
+            .flat_map(|arrivals| arrivals.values())
+            .filter_map(|hash| self.events.get(hash))
+            .flat_map(|orphan| orphan.packed_event.parents())
+            .filter(|hash| !self.events.contains_key(*hash))
+            .cloned()
+            .collect()
+    }
+}
+
+// Version of the format written by `Parsec::snapshot`. Bump on any change to `Snapshot` or to the
+// serialised representation of its fields.
+pub(crate) const SNAPSHOT_VERSION: u32 = 1;
+
+// Contents of `Parsec` as serialised by `Parsec::snapshot`.
+#[serde(bound = "")]
+#[derive(Serialize)]
+struct SnapshotRef<'a, T: NetworkEvent, S: SecretId> {
+    peer_list: &'a PeerList<S>,
+    graph: &'a Graph<S::PublicId>,
+    observations: &'a ObservationStore<T, S::PublicId>,
+    consensused_blocks: &'a VecDeque<BlockGroup<T, S::PublicId>>,
+    meta_election: &'a MetaElection,
+    consensus_mode: ConsensusMode,
+    #[cfg(feature = "malice-detection")]
+    pending_accusations: &'a Accusations<T, S::PublicId>,
+    pending_events: &'a [PendingEvent<T, S::PublicId>],
+    orphan_events: &'a OrphanPool<T, S::PublicId>,
+    published_coin_shares: &'a BTreeSet<Hash>,
+    checkpoint_frontiers: &'a PeerIndexMap<EventIndex>,
+    added_peers: &'a PeerIndexSet,
+}
+
+// Contents of `Parsec` as deserialised by `Parsec::restore`. Must match `SnapshotRef`.
+#[serde(bound = "")]
+#[derive(Serialize, Deserialize)]
+struct Snapshot<T: NetworkEvent, P: PublicId> {
+    peer_list: (Peer<P>, Vec<Peer<P>>),
+    graph: Graph<P>,
+    observations: ObservationStore<T, P>,
+    consensused_blocks: VecDeque<BlockGroup<T, P>>,
+    meta_election: MetaElection,
+    consensus_mode: ConsensusMode,
+    #[cfg(feature = "malice-detection")]
+    pending_accusations: Accusations<T, P>,
+    pending_events: Vec<PendingEvent<T, P>>,
+    orphan_events: OrphanPool<T, P>,
+    published_coin_shares: BTreeSet<Hash>,
+    checkpoint_frontiers: PeerIndexMap<EventIndex>,
+    added_peers: PeerIndexSet,
+}
+
+#[serde(bound = "")]
+#[derive(Serialize, Deserialize)]
 enum PendingEvent<T: NetworkEvent, P: PublicId> {
     Sync {
         is_request: bool,
@@ -2277,6 +4226,17 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     pub(crate) fn ignore_process_events(&self) -> bool {
         self.ignore_process_events
     }
+
+    // Return the previous voters if the current meta-election is a joint-consensus transition.
+    pub(crate) fn previous_voters(&self) -> Option<Vec<S::PublicId>> {
+        self.meta_election.previous_voters().map(|previous_voters| {
+            previous_voters
+                .keys()
+                .filter_map(|peer_index| self.peer_list.get(peer_index))
+                .map(|peer| peer.id().clone())
+                .collect()
+        })
+    }
 }
 
 #[cfg(any(feature = "testing", all(test, feature = "mock")))]
@@ -2338,6 +4298,19 @@ impl<T: NetworkEvent, S: SecretId> TestParsec<T, S> {
         TestParsec(Parsec::from_genesis(our_id, genesis_group, consensus_mode))
     }
 
+    #[cfg(all(test, feature = "mock"))]
+    pub fn from_weighted_genesis(
+        our_id: S,
+        genesis_weights: &BTreeMap<S::PublicId, u64>,
+        consensus_mode: ConsensusMode,
+    ) -> Self {
+        TestParsec(Parsec::from_weighted_genesis(
+            our_id,
+            genesis_weights,
+            consensus_mode,
+        ))
+    }
+
     pub fn from_existing(
         our_id: S,
         genesis_group: &BTreeSet<S::PublicId>,
@@ -2352,6 +4325,20 @@ impl<T: NetworkEvent, S: SecretId> TestParsec<T, S> {
         ))
     }
 
+    #[cfg(all(test, feature = "mock"))]
+    pub fn from_checkpoint(
+        our_id: S,
+        checkpoint: &Checkpoint<S::PublicId>,
+        consensus_mode: ConsensusMode,
+    ) -> Result<Self> {
+        Parsec::from_checkpoint(our_id, checkpoint, consensus_mode).map(TestParsec)
+    }
+
+    #[cfg(all(test, feature = "mock"))]
+    pub fn restore(our_id: S, bytes: &[u8]) -> Result<Self> {
+        Parsec::restore(our_id, bytes).map(TestParsec)
+    }
+
     pub fn graph(&self) -> &Graph<S::PublicId> {
         &self.0.graph
     }
@@ -159,6 +159,7 @@ mod tests {
     pub(crate) enum AssertObservationKey {
         Single(u8, PeerIndex),
         Supermajority(u8),
+        Threshold(u8, u8),
     }
 
     impl AssertObservationKey {
@@ -170,6 +171,10 @@ mod tests {
                 ObservationKey::Supermajority(ref hash) => {
                     AssertObservationKey::Supermajority(find_observation_hash_index(hash, hashes))
                 }
+                ObservationKey::Threshold(ref hash, percent) => AssertObservationKey::Threshold(
+                    find_observation_hash_index(hash, hashes),
+                    percent,
+                ),
             }
         }
     }
@@ -28,6 +28,7 @@ use crate::{
     hash::Hash,
     id::SecretId,
 };
+use serde::{Serialize, Serializer};
 use std::{
     collections::btree_map::{BTreeMap, Entry},
     fmt::{self, Debug, Formatter},
@@ -53,16 +54,58 @@ impl<S: SecretId> PeerList<S> {
         }
     }
 
+    /// Rebuilds the peer list from `our_peer` and `peers` previously serialised as part of a peer
+    /// list. Returns `None` if `our_peer` doesn't belong to `our_id`.
+    pub fn restore(
+        our_id: S,
+        our_peer: Peer<S::PublicId>,
+        peers: Vec<Peer<S::PublicId>>,
+    ) -> Option<Self> {
+        if !our_peer.has_id(our_id.public_id()) {
+            return None;
+        }
+
+        let indices = peers
+            .iter()
+            .enumerate()
+            .flat_map(|(position, peer)| {
+                iter::once(peer.id())
+                    .chain(peer.rekeyed_ids())
+                    .map(move |id| (id.clone(), PeerIndex(position + 1)))
+            })
+            .collect();
+
+        Some(PeerList {
+            our_id,
+            our_peer,
+            peers,
+            indices,
+        })
+    }
+
     pub fn our_id(&self) -> &S {
         &self.our_id
     }
 
+    /// Returns the id identifying us in the gossip events. It stays the same when we rotate our
+    /// key, unlike `our_id().public_id()`.
     pub fn our_pub_id(&self) -> &S::PublicId {
-        &self.our_id.public_id()
+        self.our_peer.id()
+    }
+
+    /// Replaces our secret id with `our_id`, once we reached consensus on rotating our key to it.
+    /// Returns `false` if we didn't.
+    pub fn set_our_id(&mut self, our_id: S) -> bool {
+        if self.our_peer.rekeyed_ids().contains(our_id.public_id()) {
+            self.our_id = our_id;
+            true
+        } else {
+            false
+        }
     }
 
     pub fn get_index(&self, peer_id: &S::PublicId) -> Option<PeerIndex> {
-        if peer_id == self.our_id.public_id() {
+        if self.our_peer.has_id(peer_id) {
             Some(PeerIndex::OUR)
         } else {
             self.indices.get(peer_id).cloned()
@@ -70,7 +113,7 @@ impl<S: SecretId> PeerList<S> {
     }
 
     pub fn contains(&self, peer_id: &S::PublicId) -> bool {
-        peer_id == self.our_id.public_id() || self.indices.contains_key(peer_id)
+        self.our_peer.has_id(peer_id) || self.indices.contains_key(peer_id)
     }
 
     pub fn get(&self, index: PeerIndex) -> Option<&Peer<S::PublicId>> {
@@ -161,7 +204,7 @@ impl<S: SecretId> PeerList<S> {
 
     /// Adds a peer in the given state into the map.
     pub fn add_peer(&mut self, peer_id: S::PublicId, state: PeerState) -> PeerIndex {
-        if peer_id == *self.our_id.public_id() {
+        if self.our_peer.has_id(&peer_id) {
             log_or_panic!(
                 "{:?} already has self in the peer list",
                 self.our_id.public_id(),
@@ -205,6 +248,49 @@ impl<S: SecretId> PeerList<S> {
         }
     }
 
+    /// Sets the voting weight of the peer.
+    pub fn set_peer_weight(&mut self, index: PeerIndex, weight: u64) {
+        if let Some(peer) = self.get_known_mut(index) {
+            peer.set_weight(weight);
+        }
+    }
+
+    /// Records that the peer signed its event at `index_by_creator` with the id at position `key`
+    /// among its ids, so that it can't sign its later events with an older one.
+    pub fn record_signing_key(&mut self, index: PeerIndex, key: usize, index_by_creator: usize) {
+        if let Some(peer) = self.get_known_mut(index) {
+            peer.record_signing_key(key, index_by_creator);
+        }
+    }
+
+    /// Rotates the key of the peer to `new_id`, keeping its index. Returns `false` if `new_id`
+    /// already belongs to a different peer.
+    pub fn rekey_peer(&mut self, index: PeerIndex, new_id: S::PublicId) -> bool {
+        if self
+            .get_index(&new_id)
+            .map(|other_index| other_index != index)
+            .unwrap_or(false)
+        {
+            return false;
+        }
+
+        if let Some(peer) = self.get_known_mut(index) {
+            peer.rekey(new_id.clone());
+        } else {
+            return false;
+        }
+        if index != PeerIndex::OUR {
+            let _ = self.indices.insert(new_id, index);
+        }
+
+        true
+    }
+
+    /// Returns the voting weight of the peer, or zero if the peer is unknown.
+    pub fn peer_weight(&self, index: PeerIndex) -> u64 {
+        self.get(index).map(Peer::weight).unwrap_or(0)
+    }
+
     /// Returns the index of the last event created by this peer. Returns `None` if cannot find.
     pub fn last_event(&self, peer_index: PeerIndex) -> Option<EventIndex> {
         self.get(peer_index)
@@ -275,6 +361,14 @@ impl<S: SecretId> PeerList<S> {
         }
     }
 
+    /// Forgets all events whose topological index is less than `end_index`.
+    pub fn prune_events(&mut self, end_index: usize) {
+        self.our_peer.prune_events(end_index);
+        for peer in &mut self.peers {
+            peer.prune_events(end_index);
+        }
+    }
+
     /// Indices of events of the given creator, in insertion order.
     pub fn peer_events<'a>(
         &'a self,
@@ -289,6 +383,13 @@ impl<S: SecretId> PeerList<S> {
     }
 }
 
+// Serialises everything except our secret id. Use `PeerList::restore` to deserialise.
+impl<S: SecretId> Serialize for PeerList<S> {
+    fn serialize<D: Serializer>(&self, serializer: D) -> Result<D::Ok, D::Error> {
+        (&self.our_peer, &self.peers).serialize(serializer)
+    }
+}
+
 impl<S: SecretId> Debug for PeerList<S> {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         writeln!(
@@ -339,6 +440,7 @@ impl PeerList<PeerId> {
 pub(crate) enum PeerListChange {
     Add(PeerIndex),
     Remove(PeerIndex),
+    Reweight(PeerIndex),
 }
 
 #[cfg(test)]
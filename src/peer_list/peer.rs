@@ -15,15 +15,27 @@ use crate::{
 };
 use itertools::Itertools;
 use std::{
+    cmp,
     fmt::{self, Debug, Formatter},
     iter::{self, FromIterator},
 };
 
-#[derive(Debug)]
+#[serde(bound = "")]
+#[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct Peer<P: PublicId> {
     id: P,
     id_hash: Hash,
+    // Ids the peer rotated its key to by consensus on `Observation::Rekey`, oldest first. `id`
+    // keeps identifying the peer inside the gossip events, so their hashes don't change.
+    rekeyed_ids: Vec<P>,
+    // Index-by-creator of the first event of the peer we received signed with each of
+    // `rekeyed_ids`, if any. The peer never signs with an older id again, so its events from that
+    // index on are rejected if they are.
+    first_rekeyed_events: Vec<Option<usize>>,
     presence: Presence,
+    // Voting weight of the peer. Supermajority means more than two thirds of the total weight of
+    // the voters.
+    weight: u64,
     pub(super) events: Events,
     pub(super) last_gossiped_event: Option<EventIndex>,
     // As a performance optimisation we keep track of which events we've cleared for Accomplice
@@ -39,7 +51,10 @@ impl<P: PublicId> Peer<P> {
         Self {
             id,
             id_hash,
+            rekeyed_ids: Vec::new(),
+            first_rekeyed_events: Vec::new(),
             presence: Presence::Present(state),
+            weight: 1,
             events: Events::new(),
             last_gossiped_event: None,
             #[cfg(feature = "malice-detection")]
@@ -51,10 +66,75 @@ impl<P: PublicId> Peer<P> {
         &self.id
     }
 
+    pub fn last_gossiped_event(&self) -> Option<EventIndex> {
+        self.last_gossiped_event
+    }
+
     pub fn id_hash(&self) -> &Hash {
         &self.id_hash
     }
 
+    // The id the peer signs with, as of the last consensused `Observation::Rekey`.
+    pub fn current_id(&self) -> &P {
+        self.rekeyed_ids.last().unwrap_or(&self.id)
+    }
+
+    // Returns whether `id` is the original id of the peer or one it rotated its key to.
+    pub fn has_id(&self, id: &P) -> bool {
+        self.id == *id || self.rekeyed_ids.contains(id)
+    }
+
+    // Returns the id of the peer which produced `signature` of `data`, if any.
+    pub fn signer(&self, signature: &P::Signature, data: &[u8]) -> Option<&P> {
+        self.signing_key(signature, data)
+            .map(|key| self.rekeyed_ids[..key].last().unwrap_or(&self.id))
+    }
+
+    // Returns the position of the id which produced `signature` of `data` among the ids of the
+    // peer, starting from zero for its original id, if any.
+    pub fn signing_key(&self, signature: &P::Signature, data: &[u8]) -> Option<usize> {
+        self.rekeyed_ids
+            .iter()
+            .rev()
+            .chain(iter::once(&self.id))
+            .position(|id| id.verify_signature(signature, data))
+            .map(|position| self.rekeyed_ids.len() - position)
+    }
+
+    // Returns whether the id at position `key` may sign the event of the peer at
+    // `index_by_creator`, i.e. whether we didn't receive an event up to that index signed with a
+    // later id.
+    pub fn can_sign_with(&self, key: usize, index_by_creator: usize) -> bool {
+        self.first_rekeyed_events
+            .iter()
+            .skip(key)
+            .flatten()
+            .all(|first_index| index_by_creator < *first_index)
+    }
+
+    // Records that the peer signed its event at `index_by_creator` with the id at position `key`.
+    pub(super) fn record_signing_key(&mut self, key: usize, index_by_creator: usize) {
+        if let Some(first_index) = key
+            .checked_sub(1)
+            .and_then(|position| self.first_rekeyed_events.get_mut(position))
+        {
+            *first_index = Some(first_index.map_or(index_by_creator, |first_index| {
+                cmp::min(first_index, index_by_creator)
+            }));
+        }
+    }
+
+    pub(super) fn rekey(&mut self, id: P) {
+        if !self.has_id(&id) {
+            self.rekeyed_ids.push(id);
+            self.first_rekeyed_events.push(None);
+        }
+    }
+
+    pub(super) fn rekeyed_ids(&self) -> &[P] {
+        &self.rekeyed_ids
+    }
+
     pub fn state(&self) -> PeerState {
         match self.presence {
             Presence::Present(state) => state,
@@ -68,6 +148,14 @@ impl<P: PublicId> Peer<P> {
         }
     }
 
+    pub fn weight(&self) -> u64 {
+        self.weight
+    }
+
+    pub(super) fn set_weight(&mut self, weight: u64) {
+        self.weight = weight;
+    }
+
     pub(super) fn set_removed(&mut self, deciding_event_index: EventIndex) {
         self.presence = Presence::Removed(deciding_event_index)
     }
@@ -102,39 +190,67 @@ impl<P: PublicId> Peer<P> {
     pub(super) fn remove_last_event(&mut self) -> Option<EventIndex> {
         self.events.remove_last()
     }
+
+    pub(super) fn prune_events(&mut self, end_index: usize) {
+        self.events.prune(end_index);
+        if self
+            .last_gossiped_event
+            .map(|event_index| event_index.topological_index() < end_index)
+            .unwrap_or(false)
+        {
+            self.last_gossiped_event = None;
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 enum Presence {
     Present(PeerState),
     // Contains the index of the event at which we reached the consensus on the removal.
     Removed(EventIndex),
 }
 
-#[derive(Debug)]
-pub(super) struct Events(Vec<Slot>);
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct Events {
+    slots: Vec<Slot>,
+    // Index-by-creator of the event(s) in the first slot. Non-zero only after pruning.
+    start: usize,
+    // Events with topological index less than this have been pruned from the graph.
+    pruned_before: usize,
+}
 
 impl Events {
     fn new() -> Self {
-        Events(Vec::new())
+        Events {
+            slots: Vec::new(),
+            start: 0,
+            pruned_before: 0,
+        }
     }
 
     fn add(&mut self, index_by_creator: usize, event_index: EventIndex) {
-        if let Some(slot) = self.0.get_mut(index_by_creator) {
+        let position = if let Some(position) = index_by_creator.checked_sub(self.start) {
+            position
+        } else {
+            log_or_panic!("Peer events must not be added below the pruned ones");
+            return;
+        };
+
+        if let Some(slot) = self.slots.get_mut(position) {
             slot.add(event_index);
             return;
         }
 
-        if index_by_creator != self.0.len() {
+        if position != self.slots.len() {
             log_or_panic!("Peer events must be added sequentially");
         }
 
-        self.0.push(Slot::new(event_index))
+        self.slots.push(Slot::new(event_index))
     }
 
     #[cfg(any(all(test, feature = "mock"), feature = "testing"))]
     fn remove_last(&mut self) -> Option<EventIndex> {
-        if let Some(slot) = self.0.last_mut() {
+        if let Some(slot) = self.slots.last_mut() {
             if let Some(index) = slot.rest.pop() {
                 return Some(index);
             }
@@ -142,29 +258,54 @@ impl Events {
             return None;
         }
 
-        self.0.pop().map(|slot| slot.first)
+        self.slots.pop().map(|slot| slot.first)
+    }
+
+    // Forget the events with topological index less than `end_index`.
+    fn prune(&mut self, end_index: usize) {
+        let pruned_slots = self
+            .slots
+            .iter()
+            .take_while(|slot| {
+                slot.iter()
+                    .all(|event_index| event_index.topological_index() < end_index)
+            })
+            .count();
+        let _ = self.slots.drain(..pruned_slots);
+        self.start += pruned_slots;
+        self.pruned_before = cmp::max(self.pruned_before, end_index);
     }
 
     fn iter<'a>(&'a self) -> impl DoubleEndedIterator<Item = EventIndex> + 'a {
-        self.0.iter().flat_map(Slot::iter)
+        let pruned_before = self.pruned_before;
+        self.slots
+            .iter()
+            .flat_map(Slot::iter)
+            .filter(move |event_index| event_index.topological_index() >= pruned_before)
     }
 
     #[cfg(all(test, feature = "mock"))]
     fn indexed<'a>(&'a self) -> impl DoubleEndedIterator<Item = (usize, EventIndex)> + 'a {
-        self.0
+        let start = self.start;
+        let pruned_before = self.pruned_before;
+        self.slots
             .iter()
             .enumerate()
-            .flat_map(|(index_by_creator, slot)| {
+            .flat_map(move |(position, slot)| {
                 slot.iter()
-                    .map(move |event_index| (index_by_creator, event_index))
+                    .filter(move |event_index| event_index.topological_index() >= pruned_before)
+                    .map(move |event_index| (start + position, event_index))
             })
     }
 
     fn by_index<'a>(&'a self, index_by_creator: usize) -> impl Iterator<Item = EventIndex> + 'a {
-        self.0
-            .get(index_by_creator)
+        let pruned_before = self.pruned_before;
+        index_by_creator
+            .checked_sub(self.start)
+            .and_then(|position| self.slots.get(position))
             .into_iter()
             .flat_map(Slot::iter)
+            .filter(move |event_index| event_index.topological_index() >= pruned_before)
     }
 }
 
@@ -185,6 +326,7 @@ where
     }
 }
 
+#[derive(Serialize, Deserialize)]
 struct Slot {
     first: EventIndex,
     rest: Vec<EventIndex>,
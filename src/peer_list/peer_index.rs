@@ -12,7 +12,7 @@ use std::{
     iter::FromIterator,
 };
 
-#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
 pub(crate) struct PeerIndex(pub(super) usize);
 
 impl PeerIndex {
@@ -26,7 +26,7 @@ impl PeerIndex {
 }
 
 /// Map keyed by `PeerIndex`.
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) struct PeerIndexMap<T>(Vec<Option<T>>);
 
 impl<T> PeerIndexMap<T> {
@@ -199,7 +199,7 @@ impl<'a, T> VacantEntry<'a, T> {
 }
 
 /// Set of `PeerIndex`.
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) struct PeerIndexSet(Vec<bool>);
 
 impl PeerIndexSet {
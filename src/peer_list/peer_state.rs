@@ -20,7 +20,7 @@ use std::{
 ///           others. For others it means we can send gossips to them.
 ///
 /// If all three are enabled, the state is called `active`. If none is enabled, it's `inactive`.
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PeerState(u8);
 
 impl PeerState {
@@ -0,0 +1,58 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{hash::Hash, id::PublicId};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Counters describing the state and the activity of a [Parsec](struct.Parsec.html) instance,
+/// returned by [stats](struct.Parsec.html#method.stats).
+///
+/// The activity counters (coin tosses, gossip bytes and accusations) start from zero whenever an
+/// instance is created or restored from a snapshot.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Stats<P: PublicId> {
+    /// Number of events in the gossip graph.
+    pub graph_size: usize,
+    /// Number of events waiting to be inserted into the gossip graph once the owning peer becomes
+    /// a voter.
+    pub pending_events: usize,
+    /// Number of observations voted for but not yet consensused.
+    pub unconsensused_observations: usize,
+    /// Most advanced meta-vote in the current meta-election, by the voter it is about.
+    pub meta_votes: BTreeMap<P, MetaVoteProgress>,
+    /// Number of distinct coin tosses resolved while computing meta-votes, i.e. at most one per
+    /// voter, round and meta-election.
+    pub coin_tosses: u64,
+    /// Total serialised size of the gossip messages created by the owning peer.
+    pub gossip_bytes_sent: u64,
+    /// Total serialised size of the gossip messages received by the owning peer.
+    pub gossip_bytes_received: u64,
+    /// Number of accusations the owning peer has raised against malicious peers. Always zero
+    /// unless the `malice-detection` feature is enabled.
+    pub accusations_raised: u64,
+}
+
+/// Round and step of a meta-vote, as defined in the PARSEC whitepaper.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct MetaVoteProgress {
+    /// The round, starting from zero.
+    pub round: usize,
+    /// The step within the round: 0 (forced true), 1 (forced false) or 2 (genuine coin flip).
+    pub step: usize,
+}
+
+// Activity counters of a `Parsec` instance.
+#[derive(Default)]
+pub(crate) struct Counters {
+    pub coin_tosses: u64,
+    // Round hashes of the coins tossed in the current meta-election, so each is counted once.
+    pub tossed_coins: BTreeSet<Hash>,
+    pub gossip_bytes_sent: u64,
+    pub gossip_bytes_received: u64,
+    pub accusations_raised: u64,
+}
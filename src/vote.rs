@@ -17,19 +17,48 @@ use crate::{
 use serde::de::DeserializeOwned;
 use std::fmt::{self, Debug, Formatter};
 
-/// A helper struct carrying an `Observation` and a signature of this `Observation`.
+/// A helper struct carrying an `Observation` and a signature of this `Observation` together with
+/// the consensus mode and the expiry of the vote.
 #[serde(bound(deserialize = "T: DeserializeOwned"))]
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Vote<T: NetworkEvent, P: PublicId> {
     payload: Observation<T, P>,
     signature: P::Signature,
+    consensus_mode: Option<ConsensusMode>,
+    expiry: Option<usize>,
 }
 
 impl<T: NetworkEvent, P: PublicId> Vote<T, P> {
     /// Creates a `Vote` for `payload`.
     pub fn new<S: SecretId<PublicId = P>>(secret_id: &S, payload: Observation<T, P>) -> Self {
-        let signature = secret_id.sign_detached(&serialise(&payload));
-        Self { payload, signature }
+        Self::new_with_terms(secret_id, payload, None, None)
+    }
+
+    /// Creates a `Vote` for `payload` which is consensused according to `consensus_mode` rather
+    /// than to the consensus mode of the section.
+    pub fn new_with_mode<S: SecretId<PublicId = P>>(
+        secret_id: &S,
+        payload: Observation<T, P>,
+        consensus_mode: ConsensusMode,
+    ) -> Self {
+        Self::new_with_terms(secret_id, payload, Some(consensus_mode), None)
+    }
+
+    // Creates a `Vote` for `payload` with the given consensus mode, which expires once `expiry`
+    // payloads have been consensused in total. Both are signed along with the payload.
+    pub(crate) fn new_with_terms<S: SecretId<PublicId = P>>(
+        secret_id: &S,
+        payload: Observation<T, P>,
+        consensus_mode: Option<ConsensusMode>,
+        expiry: Option<usize>,
+    ) -> Self {
+        let signature = secret_id.sign_detached(&signed_data(&payload, consensus_mode, expiry));
+        Self {
+            payload,
+            signature,
+            consensus_mode,
+            expiry,
+        }
     }
 
     /// Returns the payload being voted for.
@@ -37,14 +66,33 @@ impl<T: NetworkEvent, P: PublicId> Vote<T, P> {
         &self.payload
     }
 
-    /// Returns the signature of this `Vote`'s payload.
+    /// Returns the signature of this `Vote`'s payload, consensus mode and expiry.
     pub fn signature(&self) -> &P::Signature {
         &self.signature
     }
 
-    /// Validates this `Vote`'s signature and payload against the given public ID.
+    /// Returns the consensus mode chosen by the voter, or `None` if the vote follows the consensus
+    /// mode of the section. Only applies to `Observation::OpaquePayload`; the other observations
+    /// always need a supermajority.
+    pub fn consensus_mode(&self) -> Option<ConsensusMode> {
+        self.consensus_mode
+    }
+
+    /// Returns the number of consensused payloads from which this vote is ignored if its payload
+    /// hasn't been consensused by then, or `None` if the vote never expires.
+    pub fn expiry(&self) -> Option<usize> {
+        self.expiry
+    }
+
+    /// Validates this `Vote`'s signature, payload, consensus mode and expiry against the given
+    /// public ID.
     pub fn is_valid(&self, public_id: &P) -> bool {
-        public_id.verify_signature(&self.signature, &serialise(&self.payload))
+        public_id.verify_signature(&self.signature, &self.signed_data())
+    }
+
+    // Returns the data signed by the voter.
+    pub(crate) fn signed_data(&self) -> Vec<u8> {
+        signed_data(&self.payload, self.consensus_mode, self.expiry)
     }
 
     /// Creates a `Proof` from this `Vote`.  Returns `Err` if this `Vote` is not valid (i.e. if
@@ -60,6 +108,15 @@ impl<T: NetworkEvent, P: PublicId> Vote<T, P> {
     }
 }
 
+// Returns the data signed by a voter for a vote with the given payload, consensus mode and expiry.
+pub(crate) fn signed_data<T: NetworkEvent, P: PublicId>(
+    payload: &Observation<T, P>,
+    consensus_mode: Option<ConsensusMode>,
+    expiry: Option<usize>,
+) -> Vec<u8> {
+    serialise(&(payload, consensus_mode, expiry))
+}
+
 impl<T: NetworkEvent, P: PublicId> Debug for Vote<T, P> {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "{:?}", self.payload)
@@ -67,28 +124,41 @@ impl<T: NetworkEvent, P: PublicId> Debug for Vote<T, P> {
 }
 
 /// Key representing a vote when stored inside the gossip graph.
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[serde(bound = "")]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
 pub(crate) struct VoteKey<P: PublicId> {
     payload_key: ObservationKey,
     signature: P::Signature,
+    consensus_mode: Option<ConsensusMode>,
+    expiry: Option<usize>,
 }
 
 impl<P: PublicId> VoteKey<P> {
+    /// Returns `Error::InvalidConsensusMode` if the vote requests a threshold of 100% or more, which
+    /// could never be reached.
     pub fn new<T: NetworkEvent>(
         vote: Vote<T, P>,
         creator: PeerIndex,
         consensus_mode: ConsensusMode,
-    ) -> (Self, Observation<T, P>) {
-        let consensus_mode = consensus_mode.of(&vote.payload);
+    ) -> Result<(Self, Observation<T, P>), Error> {
+        let payload_consensus_mode = vote
+            .consensus_mode
+            .unwrap_or(consensus_mode)
+            .of(&vote.payload);
+        if !payload_consensus_mode.is_valid() {
+            return Err(Error::InvalidConsensusMode);
+        }
         let hash = ObservationHash::from(&vote.payload);
-        let payload_key = ObservationKey::new(hash, creator, consensus_mode);
+        let payload_key = ObservationKey::new(hash, creator, payload_consensus_mode);
 
         let vote_key = Self {
             payload_key,
             signature: vote.signature,
+            consensus_mode: vote.consensus_mode,
+            expiry: vote.expiry,
         };
 
-        (vote_key, vote.payload)
+        Ok((vote_key, vote.payload))
     }
 
     /// Fetch the `Vote` corresponding to `key`.
@@ -102,12 +172,18 @@ impl<P: PublicId> VoteKey<P> {
                 .map(|info| info.observation.clone())
                 .ok_or(Error::UnknownPayload)?,
             signature: self.signature.clone(),
+            consensus_mode: self.consensus_mode,
+            expiry: self.expiry,
         })
     }
 
     pub fn payload_key(&self) -> &ObservationKey {
         &self.payload_key
     }
+
+    pub fn expiry(&self) -> Option<usize> {
+        self.expiry
+    }
 }
 
 impl<P: PublicId> Debug for VoteKey<P> {
@@ -350,6 +350,38 @@ fn consensus_mode_single() {
     unwrap!(env.network.execute_schedule(&mut env.rng, schedule));
 }
 
+#[test]
+fn joint_consensus_dynamic_membership() {
+    use parsec::dev_utils::ObservationEvent::*;
+
+    let mut env = Environment::new(SEED);
+    env.network.set_joint_consensus(true);
+    let obs_schedule = ObservationSchedule {
+        genesis: Genesis::new(NAMES.iter().take(6).cloned().map(PeerId::new).collect()),
+        schedule: vec![
+            // A removal is only allowed once the remaining peers form a supermajority of both the
+            // previous and the current voters, so each one is followed by another payload which
+            // ends the transition it may have to wait for.
+            (50, AddPeer(PeerId::new("Gina"))),
+            (100, Opaque(Transaction::new("one"))),
+            (200, RemovePeer(PeerId::new("Alice"))),
+            (300, Opaque(Transaction::new("two"))),
+            (400, AddPeer(PeerId::new("Hank"))),
+            (400, RemovePeer(PeerId::new("Bob"))),
+            (500, Opaque(Transaction::new("three"))),
+            (600, AddPeer(PeerId::new("Iris"))),
+            (700, RemovePeer(PeerId::new("Carol"))),
+            (800, Opaque(Transaction::new("four"))),
+            (1000, Opaque(Transaction::new("five"))),
+        ],
+    };
+    let options = ScheduleOptions::default();
+    let schedule = Schedule::from_observation_schedule(&mut env, &options, obs_schedule);
+
+    let result = env.network.execute_schedule(&mut env.rng, schedule);
+    assert!(result.is_ok(), "{:?}", result);
+}
+
 #[test]
 fn extensive_dynamic_membership() {
     use parsec::dev_utils::ObservationEvent::*;